@@ -0,0 +1,156 @@
+use actix_web::http::header::HeaderValue;
+use actix_web::http::header::{RETRY_AFTER, WWW_AUTHENTICATE};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use derive_more::Display;
+use log::{error, info};
+use serde::Serialize;
+use std::fmt;
+use uuid::Uuid;
+
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Error body, as defined by RFC 7807
+#[derive(Serialize)]
+struct Problem<'a> {
+    r#type: &'a str,
+    title: &'a str,
+    status: u16,
+    detail: String,
+    /// Identifies the error in the server logs
+    error_id: Uuid,
+}
+
+/// Answers an error as an `application/problem+json` document
+fn problem_response(status: StatusCode, detail: String, error_id: Uuid) -> HttpResponse {
+    HttpResponse::build(status)
+        .content_type(PROBLEM_CONTENT_TYPE)
+        .json(Problem {
+            r#type: "about:blank",
+            title: status.canonical_reason().unwrap_or_default(),
+            status: status.as_u16(),
+            detail,
+            error_id,
+        })
+}
+
+/// Logs a client error under a new id, and answers it
+fn client_error_response(status: StatusCode, detail: String) -> HttpResponse {
+    let error_id = Uuid::new_v4();
+    info!("[{}] {} : {}", error_id, status, detail);
+    problem_response(status, detail, error_id)
+}
+
+/// Errors answered by the API routes
+#[derive(Debug, Display)]
+pub enum ApiError {
+    #[display(fmt = "{}", _0)]
+    BadRequest(String),
+    /// Missing or rejected credentials. `invalid_token` is set when a token was sent
+    #[display(fmt = "{}", detail)]
+    Unauthorized { detail: String, invalid_token: bool },
+    #[display(fmt = "{}", _0)]
+    Forbidden(String),
+    #[display(fmt = "{}", _0)]
+    NotFound(String),
+    #[display(fmt = "{}", _0)]
+    Conflict(String),
+    /// Token quota exceeded. `retry_after` is in seconds
+    #[display(fmt = "{}", detail)]
+    TooManyRequests { detail: String, retry_after: u64 },
+    #[display(fmt = "{}", _0)]
+    Unavailable(String),
+    /// Unexpected failure. The client only gets the id of the error, logged with its cause
+    #[display(fmt = "Error {} : contact your administrator", _0)]
+    Internal(Uuid),
+}
+
+impl ApiError {
+    /// Logs an unexpected error under a new id
+    pub fn internal(request: &str, err: impl fmt::Display) -> ApiError {
+        let error_id = Uuid::new_v4();
+        error!(
+            "[{}] Error while answering request {} : {}",
+            error_id, request, err
+        );
+        ApiError::Internal(error_id)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            ApiError::Internal(error_id) => {
+                problem_response(self.status_code(), self.to_string(), *error_id)
+            }
+            ApiError::Unauthorized { invalid_token, .. } => {
+                // Required by RFC 7235, with the error code of RFC 6750
+                let challenge = match invalid_token {
+                    true => "Bearer error=\"invalid_token\"",
+                    false => "Bearer",
+                };
+                let mut response = client_error_response(self.status_code(), self.to_string());
+                response
+                    .headers_mut()
+                    .insert(WWW_AUTHENTICATE, HeaderValue::from_static(challenge));
+                response
+            }
+            ApiError::TooManyRequests { retry_after, .. } => {
+                let mut response = client_error_response(self.status_code(), self.to_string());
+                response
+                    .headers_mut()
+                    .insert(RETRY_AFTER, (*retry_after).into());
+                response
+            }
+            _ => client_error_response(self.status_code(), self.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unauthorized(invalid_token: bool) -> HttpResponse {
+        ApiError::Unauthorized {
+            detail: "Access denied".to_string(),
+            invalid_token,
+        }
+        .error_response()
+    }
+
+    #[test]
+    fn unauthorized_answers_a_bearer_challenge() {
+        let response = unauthorized(false);
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers().get(WWW_AUTHENTICATE).unwrap(), "Bearer");
+    }
+
+    #[test]
+    fn unauthorized_with_a_rejected_token_flags_it_as_invalid() {
+        let response = unauthorized(true);
+        assert_eq!(
+            response.headers().get(WWW_AUTHENTICATE).unwrap(),
+            "Bearer error=\"invalid_token\""
+        );
+    }
+
+    #[test]
+    fn other_errors_have_no_challenge() {
+        let response = ApiError::Forbidden("Access denied".to_string()).error_response();
+        assert!(response.headers().get(WWW_AUTHENTICATE).is_none());
+    }
+}
@@ -1,14 +1,143 @@
-pub mod config;
-pub mod db;
-pub mod messages;
+pub mod error;
 pub mod routes;
 pub mod security;
 
+use actix_cors::Cors;
+use actix_web::http::header;
+use actix_web::{web, HttpResponse};
+use error::ApiError;
 use log::info;
+use navdata::config::CorsConfig;
 
-pub fn register_routes(cfg: &mut actix_web::web::ServiceConfig) {
+pub fn register_routes(cfg: &mut web::ServiceConfig) {
     routes::airport::register_routes(cfg);
     routes::navaid::register_routes(cfg);
+    routes::meta::register_routes(cfg);
+    routes::admin::register_routes(cfg);
+
+    // Malformed query strings and unknown paths are answered as problem details too
+    cfg.app_data(
+        web::QueryConfig::default()
+            .error_handler(|err, _| ApiError::BadRequest(err.to_string()).into()),
+    );
+    cfg.default_service(web::to(|| async {
+        Err::<HttpResponse, _>(ApiError::NotFound("Unknown resource".to_string()))
+    }));
 
     info!("Routes loaded");
 }
+
+/// CORS policy. Cross-origin requests are rejected unless their origin is allowed
+pub fn cors(config: &CorsConfig) -> Cors {
+    let mut cors = Cors::default()
+        .allowed_methods(config.allowed_methods.iter().map(String::as_str))
+        .allowed_headers(config.allowed_headers.iter().map(String::as_str))
+        // Cache validators and quota delay
+        .expose_headers([header::ETAG, header::LAST_MODIFIED, header::RETRY_AFTER])
+        .max_age(config.max_age);
+    for origin in &config.allowed_origins {
+        cors = match origin.as_str() {
+            "*" => cors.allow_any_origin().send_wildcard(),
+            origin => cors.allowed_origin(origin),
+        };
+    }
+    if config.allow_credentials {
+        cors = cors.supports_credentials();
+    }
+    cors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::{Method, StatusCode};
+    use actix_web::{test, App};
+    use navdata::config::{Config, HttpConfig};
+
+    const ALLOWED: &str = "https://app.example.com";
+
+    fn allowing(origins: &[&str]) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: origins.iter().map(|origin| origin.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Status and `Access-Control-Allow-Origin` header of a request from the origin
+    async fn answer(
+        config: &CorsConfig,
+        request: test::TestRequest,
+    ) -> (StatusCode, Option<String>) {
+        let app = test::init_service(
+            App::new()
+                .wrap(cors(config))
+                .route("/airport", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let response = test::call_service(&app, request.uri("/airport").to_request()).await;
+        let allowed_origin = response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .map(|value| value.to_str().unwrap().to_string());
+        (response.status(), allowed_origin)
+    }
+
+    fn from(origin: &str) -> test::TestRequest {
+        test::TestRequest::get().insert_header((header::ORIGIN, origin))
+    }
+
+    fn preflight(origin: &str) -> test::TestRequest {
+        test::TestRequest::default()
+            .method(Method::OPTIONS)
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+    }
+
+    #[actix_rt::test]
+    async fn allowed_origins_get_the_allow_origin_header() {
+        let config = allowing(&[ALLOWED]);
+        assert_eq!(
+            answer(&config, from(ALLOWED)).await,
+            (StatusCode::OK, Some(ALLOWED.to_string()))
+        );
+        assert_eq!(
+            answer(&config, preflight(ALLOWED)).await,
+            (StatusCode::OK, Some(ALLOWED.to_string()))
+        );
+    }
+
+    /// Browsers refuse the answers without the allow origin header, and do not send
+    /// the requests whose preflight failed
+    #[actix_rt::test]
+    async fn other_origins_are_refused() {
+        let origin = "https://evil.example.com";
+        for config in [allowing(&[ALLOWED]), allowing(&[])] {
+            assert_eq!(answer(&config, from(origin)).await.1, None);
+            assert_eq!(
+                answer(&config, preflight(origin)).await,
+                (StatusCode::BAD_REQUEST, None)
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn the_wildcard_allows_any_origin_without_credentials() {
+        assert_eq!(
+            answer(&allowing(&["*"]), from("https://any.example.com")).await,
+            (StatusCode::OK, Some("*".to_string()))
+        );
+
+        let config = Config {
+            http: HttpConfig {
+                cors: CorsConfig {
+                    allow_credentials: true,
+                    ..allowing(&["*"])
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("not allowed with the * origin"));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::app::error::ApiError;
+use crate::app::security::{Authenticator, SharedSecurity};
+use actix_web::{get, post, web, HttpResponse};
+use log::{error, info};
+use navdata::db::AppState;
+use serde_json::json;
+
+pub fn register_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(refresh);
+    cfg.service(refresh_status);
+    cfg.service(token_usage);
+
+    info!("admin routes loaded");
+}
+
+#[post("/admin/refresh")]
+async fn refresh(app_state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    info!("Request received : /admin/refresh");
+
+    if app_state.database.is_refreshing() {
+        return Err(ApiError::Conflict(
+            "A data refresh is already running".to_string(),
+        ));
+    }
+
+    let state = app_state.clone();
+    actix_rt::spawn(async move {
+        if let Err(err) = state.refresh().await {
+            error!("On demand data refresh failed : {}", err);
+        }
+    });
+    Ok(HttpResponse::Accepted()
+        .json(json!({"status": "success", "description": "Data refresh started"})))
+}
+
+#[get("/admin/refresh/status")]
+async fn refresh_status(app_state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    info!("Request received : /admin/refresh/status");
+
+    let data = app_state
+        .database
+        .refresh_status()
+        .await
+        .map_err(|err| ApiError::internal("/admin/refresh/status", err))?;
+    Ok(HttpResponse::Ok().json(json!({"status": "success", "refresh" : data})))
+}
+
+#[get("/admin/tokens")]
+async fn token_usage(security: web::Data<SharedSecurity>) -> HttpResponse {
+    info!("Request received : /admin/tokens");
+
+    // JWTs are not known in advance
+    let reports = match &security.load().authenticator {
+        Authenticator::Tokens(tokens) => tokens.reports(),
+        Authenticator::Jwt(_) => Vec::new(),
+    };
+    HttpResponse::Ok().json(json!({"status": "success", "tokens" : reports}))
+}
@@ -1,9 +1,10 @@
-use crate::app::db::AppState;
-use actix_web::{get, web, HttpResponse, Responder};
-use log::{error, info};
+use super::meta::DatasetValidators;
+use crate::app::error::ApiError;
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use log::info;
+use navdata::db::{AppState, SearchFilter};
 use serde::Deserialize;
 use serde_json::json;
-use uuid::Uuid;
 
 pub fn register_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(airport_by_icao_code);
@@ -20,66 +21,80 @@ struct FormData {
     airport_type: Option<String>,
     latitude: Option<f64>,
     longitude: Option<f64>,
+    radius_nm: Option<f64>,
+    /// minLon,minLat,maxLon,maxLat
+    bbox: Option<String>,
+    limit: Option<u64>,
+    skip_details: Option<bool>,
+}
+
+impl FormData {
+    fn to_filter(&self) -> Result<SearchFilter, String> {
+        let filter = SearchFilter {
+            search: self.search.clone(),
+            page: self.page,
+            limit: self.limit,
+            country: self.country.clone(),
+            r#type: self.airport_type.clone(),
+            latitude: self.latitude,
+            longitude: self.longitude,
+            radius_nm: self.radius_nm,
+            bbox: self.bbox.as_deref().map(str::parse).transpose()?,
+            skip_details: self.skip_details.unwrap_or_default(),
+        };
+        filter.validate()?;
+        Ok(filter)
+    }
 }
 
 #[get("/airport")]
-async fn airport(param: web::Query<FormData>, app_state: web::Data<AppState>) -> impl Responder {
+async fn airport(
+    req: HttpRequest,
+    param: web::Query<FormData>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
     info!("Request received : /airport");
+    let filter = param.to_filter().map_err(ApiError::BadRequest)?;
+    let validators = DatasetValidators::new(&app_state).await?;
+    if let Some(response) = validators.not_modified(&req) {
+        return Ok(response);
+    }
     let data = app_state
         .database
-        .search_airport(
-            param.search.clone(),
-            param.page,
-            param.country.clone(),
-            param.airport_type.clone(),
-            param.latitude,
-            param.longitude,
-        )
-        .await;
-    match data {
-        Ok(data) => HttpResponse::Ok().json(json!({"status": "success", "airports" : data})),
-        Err(err) => {
-            let error_id = Uuid::new_v4();
-            error!(
-                "[{}] Error while answering request /airport : {}",
-                error_id, err
-            );
-            HttpResponse::Ok().json(json!({"status": "error", "description" : format!("Error {} : contact your administrator", error_id)}))
-        }
-    }
+        .search_airport(filter)
+        .await
+        .map_err(|err| ApiError::internal("/airport", err))?;
+    Ok(validators
+        .headers(HttpResponse::Ok())
+        .json(json!({"status": "success", "airports" : data})))
 }
 
 #[get("/airport/{icao}")]
 async fn airport_by_icao_code(
+    req: HttpRequest,
     icao: web::Path<String>,
     app_state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     info!("Request received : /airport/{}", icao);
 
     if icao.len() != 4 {
-        return HttpResponse::Ok().json(
-            json!({"status": "error", "description":"Airport ICAO codes must be 4 letter long"}),
-        );
+        return Err(ApiError::BadRequest(
+            "Airport ICAO codes must be 4 letter long".to_string(),
+        ));
+    }
+
+    let validators = DatasetValidators::new(&app_state).await?;
+    if let Some(response) = validators.not_modified(&req) {
+        return Ok(response);
     }
 
     let data = app_state
         .database
         .get_airport_by_icao_code(icao.to_string())
-        .await;
-    match data {
-        Ok(data) => match data {
-            Some(data) => {
-                HttpResponse::Ok().json(json!({"status": "success", "airport" : data, "count" : 1}))
-            }
-            None => HttpResponse::Ok().json(json!({"status": "success", "count" : 0})),
-        },
-        Err(err) => {
-            let error_id = Uuid::new_v4();
-            error!(
-                "[{}] Error while answering request /airport/{} : {}",
-                error_id, icao, err
-            );
-            HttpResponse::Ok().json(json!({"status": "error", "description" : format!("Error {} : contact your administrator", error_id)}))
-        }
-    }
+        .await
+        .map_err(|err| ApiError::internal(&format!("/airport/{}", icao), err))?
+        .ok_or_else(|| ApiError::NotFound(format!("No airport with ICAO code {}", icao)))?;
+    Ok(validators
+        .headers(HttpResponse::Ok())
+        .json(json!({"status": "success", "airport" : data, "count" : 1})))
 }
@@ -0,0 +1,72 @@
+use crate::app::error::ApiError;
+use actix_web::http::header::{self, EntityTag, Header, HttpDate, IfNoneMatch};
+use actix_web::{get, web, HttpRequest, HttpResponse, HttpResponseBuilder};
+use log::info;
+use navdata::db::AppState;
+use serde_json::json;
+use std::time::{Duration, UNIX_EPOCH};
+
+pub fn register_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(meta);
+
+    info!("meta routes loaded");
+}
+
+#[get("/meta")]
+async fn meta(app_state: web::Data<AppState>) -> Result<HttpResponse, ApiError> {
+    info!("Request received : /meta");
+
+    let data = app_state
+        .dataset_meta()
+        .await
+        .map_err(|err| ApiError::internal("/meta", err))?;
+    Ok(HttpResponse::Ok().json(json!({"status": "success", "meta" : data})))
+}
+
+/// Cache validators of the responses built from the dataset currently served
+pub struct DatasetValidators {
+    etag: Option<EntityTag>,
+    last_modified: Option<HttpDate>,
+}
+
+impl DatasetValidators {
+    /// Fails with 503 until a first data load completed
+    pub async fn new(app_state: &AppState) -> Result<DatasetValidators, ApiError> {
+        let dataset = app_state
+            .dataset_meta()
+            .await
+            .map_err(|err| ApiError::internal("dataset version", err))?;
+        if dataset.files.is_empty() {
+            return Err(ApiError::Unavailable(
+                "No data loaded yet, retry later".to_string(),
+            ));
+        }
+        Ok(DatasetValidators {
+            etag: Some(EntityTag::new_strong(dataset.version)),
+            last_modified: dataset
+                .last_update
+                .map(|date| HttpDate::from(UNIX_EPOCH + Duration::from_secs(date.max(0) as u64))),
+        })
+    }
+
+    /// Answers 304 if the client copy matches the dataset currently served
+    pub fn not_modified(&self, req: &HttpRequest) -> Option<HttpResponse> {
+        let etag = self.etag.as_ref()?;
+        let matches = match IfNoneMatch::parse(req).ok()? {
+            IfNoneMatch::Any => true,
+            IfNoneMatch::Items(items) => items.iter().any(|item| item.weak_eq(etag)),
+        };
+        matches.then(|| self.headers(HttpResponse::NotModified()).finish())
+    }
+
+    /// Adds the validators to a response
+    pub fn headers(&self, mut response: HttpResponseBuilder) -> HttpResponseBuilder {
+        if let Some(etag) = &self.etag {
+            response.insert_header(header::ETag(etag.clone()));
+        }
+        if let Some(last_modified) = self.last_modified {
+            response.insert_header(header::LastModified(last_modified));
+        }
+        response
+    }
+}
@@ -1,2 +1,4 @@
+pub mod admin;
 pub mod airport;
+pub mod meta;
 pub mod navaid;
@@ -1,9 +1,10 @@
-use crate::app::db::AppState;
-use actix_web::{get, web, HttpResponse, Responder};
-use log::{error, info};
+use super::meta::DatasetValidators;
+use crate::app::error::ApiError;
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use log::info;
+use navdata::db::{AppState, SearchFilter};
 use serde::Deserialize;
 use serde_json::json;
-use uuid::Uuid;
 
 pub fn register_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(navaid);
@@ -20,61 +21,84 @@ struct FormData {
     navaid_type: Option<String>,
     latitude: Option<f64>,
     longitude: Option<f64>,
+    radius_nm: Option<f64>,
+    /// minLon,minLat,maxLon,maxLat
+    bbox: Option<String>,
+    limit: Option<u64>,
+}
+
+impl FormData {
+    fn to_filter(&self) -> Result<SearchFilter, String> {
+        let filter = SearchFilter {
+            search: self.search.clone(),
+            page: self.page,
+            limit: self.limit,
+            country: self.country.clone(),
+            r#type: self.navaid_type.clone(),
+            latitude: self.latitude,
+            longitude: self.longitude,
+            radius_nm: self.radius_nm,
+            bbox: self.bbox.as_deref().map(str::parse).transpose()?,
+            ..Default::default()
+        };
+        filter.validate()?;
+        Ok(filter)
+    }
 }
 
 #[get("/navaid")]
-async fn navaid(param: web::Query<FormData>, app_state: web::Data<AppState>) -> impl Responder {
+async fn navaid(
+    req: HttpRequest,
+    param: web::Query<FormData>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse, ApiError> {
     info!("Request received : /navaid");
+    let filter = param.to_filter().map_err(ApiError::BadRequest)?;
+    let validators = DatasetValidators::new(&app_state).await?;
+    if let Some(response) = validators.not_modified(&req) {
+        return Ok(response);
+    }
     let data = app_state
         .database
-        .search_navaid(
-            param.search.clone(),
-            param.page,
-            param.country.clone(),
-            param.navaid_type.clone(),
-            param.latitude,
-            param.longitude,
-        )
-        .await;
-    match data {
-        Ok(data) => HttpResponse::Ok().json(json!({"status": "success", "navaid" : data})),
-        Err(err) => {
-            let error_id = Uuid::new_v4();
-            error!(
-                "[{}] Error while answering request /navaid : {}",
-                error_id, err
-            );
-            HttpResponse::Ok().json(json!({"status": "error", "description" : format!("Error {} : contact your administrator", error_id)}))
-        }
-    }
+        .search_navaid(filter)
+        .await
+        .map_err(|err| ApiError::internal("/navaid", err))?;
+    Ok(validators
+        .headers(HttpResponse::Ok())
+        .json(json!({"status": "success", "navaid" : data})))
 }
 
 #[get("/navaid/{icao}")]
 async fn navaid_by_icao_code(
+    req: HttpRequest,
     icao: web::Path<String>,
     app_state: web::Data<AppState>,
-) -> impl Responder {
+) -> Result<HttpResponse, ApiError> {
     info!("Request received : /navaid/{}", icao);
 
     if icao.len() != 3 {
-        return HttpResponse::Ok().json(
-            json!({"status": "error", "description":"Navaid ICAO codes must be 3 letter long"}),
-        );
+        return Err(ApiError::BadRequest(
+            "Navaid ICAO codes must be 3 letter long".to_string(),
+        ));
+    }
+
+    let validators = DatasetValidators::new(&app_state).await?;
+    if let Some(response) = validators.not_modified(&req) {
+        return Ok(response);
     }
 
     let data = app_state
         .database
         .get_navaids_by_icao_code(icao.to_string())
-        .await;
-    match data {
-        Ok(data) => HttpResponse::Ok().json(json!({"status": "success", "navaid" : data})),
-        Err(err) => {
-            let error_id = Uuid::new_v4();
-            error!(
-                "[{}] Error while answering request /navaid/{} : {}",
-                error_id, icao, err
-            );
-            HttpResponse::Ok().json(json!({"status": "error", "description" : format!("Error {} : contact your administrator", error_id)}))
-        }
+        .await
+        .map_err(|err| ApiError::internal(&format!("/navaid/{}", icao), err))?;
+    if data.is_empty() {
+        return Err(ApiError::NotFound(format!(
+            "No navaid with ICAO code {}",
+            icao
+        )));
     }
+    Ok(validators
+        .headers(HttpResponse::Ok())
+        .json(json!({"status": "success", "navaid" : data})))
 }
@@ -1,12 +1,86 @@
-use actix_web::ResponseError;
+use crate::app::error::ApiError;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
 use derive_more::Display;
+use navdata::config::TokenScope;
 
 #[derive(Debug, Display)]
 pub enum AuthorizationError {
+    #[display(fmt = "Access denied from {}", _0)]
+    AddressDenied(String),
     #[display(fmt = "Access denied, invalid token")]
     InvalidToken,
+    #[display(fmt = "Access denied, invalid token : {}", _0)]
+    InvalidJwt(String),
     #[display(fmt = "Access denied, no token")]
     NoToken,
+    #[display(fmt = "Access denied, expired token")]
+    Expired,
+    #[display(fmt = "Access denied, {:?} scope required", _0)]
+    MissingScope(TokenScope),
+    #[display(fmt = "Quota of {} requests per minute exceeded", limit)]
+    QuotaExceeded { limit: u32, retry_after: u64 },
 }
 
-impl ResponseError for AuthorizationError {}
+impl From<&AuthorizationError> for ApiError {
+    fn from(err: &AuthorizationError) -> ApiError {
+        match err {
+            AuthorizationError::NoToken => ApiError::Unauthorized {
+                detail: err.to_string(),
+                invalid_token: false,
+            },
+            AuthorizationError::InvalidToken
+            | AuthorizationError::InvalidJwt(_)
+            | AuthorizationError::Expired => ApiError::Unauthorized {
+                detail: err.to_string(),
+                invalid_token: true,
+            },
+            AuthorizationError::AddressDenied(_) | AuthorizationError::MissingScope(_) => {
+                ApiError::Forbidden(err.to_string())
+            }
+            AuthorizationError::QuotaExceeded { retry_after, .. } => ApiError::TooManyRequests {
+                detail: err.to_string(),
+                retry_after: *retry_after,
+            },
+        }
+    }
+}
+
+impl ResponseError for AuthorizationError {
+    fn status_code(&self) -> StatusCode {
+        ApiError::from(self).status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        ApiError::from(self).error_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::WWW_AUTHENTICATE;
+
+    fn challenge(err: AuthorizationError) -> String {
+        let response = err.error_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let challenge = response.headers().get(WWW_AUTHENTICATE).unwrap();
+        challenge.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn missing_token_gets_a_plain_challenge() {
+        assert_eq!(challenge(AuthorizationError::NoToken), "Bearer");
+    }
+
+    #[test]
+    fn rejected_tokens_get_an_invalid_token_challenge() {
+        for err in [
+            AuthorizationError::InvalidToken,
+            AuthorizationError::InvalidJwt("bad signature".to_string()),
+            AuthorizationError::Expired,
+        ] {
+            assert_eq!(challenge(err), "Bearer error=\"invalid_token\"");
+        }
+    }
+}
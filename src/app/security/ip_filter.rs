@@ -0,0 +1,186 @@
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::X_FORWARDED_FOR;
+use ipnet::IpNet;
+use navdata::config::SecurityConfig;
+use std::net::IpAddr;
+
+/// Client address resolution, and the global allow and deny lists
+pub struct IpFilter {
+    allowed: Vec<IpNet>,
+    denied: Vec<IpNet>,
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl IpFilter {
+    pub fn new(security: &SecurityConfig) -> IpFilter {
+        IpFilter {
+            allowed: security.allowed_ips.clone(),
+            denied: security.denied_ips.clone(),
+            trusted_proxies: security.trusted_proxies.clone(),
+        }
+    }
+
+    /// Address of the client. `X-Forwarded-For` is only read if the peer is a trusted
+    /// proxy. Its addresses are read from the right, as only the last ones were added
+    /// by known hosts, up to the first one which is not a trusted proxy
+    pub fn client_ip(&self, req: &ServiceRequest) -> Option<IpAddr> {
+        let mut client = req.peer_addr()?.ip().to_canonical();
+        if !contains(&self.trusted_proxies, client) {
+            return Some(client);
+        }
+        let forwarded: Vec<&str> = req
+            .headers()
+            .get_all(X_FORWARDED_FOR)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+        for address in forwarded.iter().rev() {
+            // Stop at the last trusted hop if the header was tampered with
+            let Ok(address) = address.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = address.to_canonical();
+            if !contains(&self.trusted_proxies, client) {
+                break;
+            }
+        }
+        Some(client)
+    }
+
+    pub fn allows(&self, ip: Option<IpAddr>) -> bool {
+        ip_allowed(ip, &self.allowed, &self.denied)
+    }
+}
+
+/// Denied networks take precedence. Unknown addresses are only allowed if there is
+/// no list to check them against
+pub fn ip_allowed(ip: Option<IpAddr>, allowed: &[IpNet], denied: &[IpNet]) -> bool {
+    if allowed.is_empty() && denied.is_empty() {
+        return true;
+    }
+    ip.is_some_and(|ip| !contains(denied, ip) && (allowed.is_empty() || contains(allowed, ip)))
+}
+
+pub fn display_ip(ip: Option<IpAddr>) -> String {
+    ip.map_or("unknown address".to_string(), |ip| ip.to_string())
+}
+
+fn contains(networks: &[IpNet], ip: IpAddr) -> bool {
+    networks.iter().any(|network| network.contains(&ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn ip_filter(yaml: &str) -> IpFilter {
+        IpFilter::new(&serde_yaml::from_str::<SecurityConfig>(yaml).unwrap())
+    }
+
+    fn client_ip(filter: &IpFilter, peer: &str, forwarded: Option<&str>) -> IpAddr {
+        let mut request =
+            TestRequest::default().peer_addr(format!("{}:443", peer).parse().unwrap());
+        if let Some(forwarded) = forwarded {
+            request = request.insert_header((X_FORWARDED_FOR, forwarded));
+        }
+        filter.client_ip(&request.to_srv_request()).unwrap()
+    }
+
+    fn ip(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn client_is_the_peer_without_trusted_proxies() {
+        let filter = ip_filter("{}");
+        assert_eq!(client_ip(&filter, "192.0.2.1", None), ip("192.0.2.1"));
+        assert_eq!(
+            client_ip(&filter, "192.0.2.1", Some("198.51.100.7")),
+            ip("192.0.2.1")
+        );
+    }
+
+    #[test]
+    fn forwarded_address_is_read_from_a_trusted_proxy() {
+        let filter = ip_filter("trusted_proxies: [10.0.0.0/8]");
+        assert_eq!(
+            client_ip(&filter, "10.0.0.1", Some("198.51.100.7")),
+            ip("198.51.100.7")
+        );
+        // Without header, the proxy itself is the client
+        assert_eq!(client_ip(&filter, "10.0.0.1", None), ip("10.0.0.1"));
+    }
+
+    #[test]
+    fn forwarded_addresses_are_read_from_the_right_up_to_the_first_untrusted_one() {
+        let filter = ip_filter("trusted_proxies: [10.0.0.0/8]");
+        // The client forged the first address, the trusted proxies added the last ones
+        let forwarded = "203.0.113.66, 198.51.100.7, 10.0.0.2";
+        assert_eq!(
+            client_ip(&filter, "10.0.0.1", Some(forwarded)),
+            ip("198.51.100.7")
+        );
+        // Multiple headers are read as a single list
+        let request = TestRequest::default()
+            .peer_addr("10.0.0.1:443".parse().unwrap())
+            .append_header((X_FORWARDED_FOR, "198.51.100.7"))
+            .append_header((X_FORWARDED_FOR, "10.0.0.2"))
+            .to_srv_request();
+        assert_eq!(filter.client_ip(&request), Some(ip("198.51.100.7")));
+    }
+
+    #[test]
+    fn forwarded_address_from_an_untrusted_peer_is_ignored() {
+        let filter = ip_filter("trusted_proxies: [10.0.0.1]");
+        assert_eq!(
+            client_ip(&filter, "192.0.2.1", Some("10.0.0.5")),
+            ip("192.0.2.1")
+        );
+    }
+
+    #[test]
+    fn invalid_forwarded_address_stops_at_the_last_trusted_hop() {
+        let filter = ip_filter("trusted_proxies: [10.0.0.0/8]");
+        assert_eq!(
+            client_ip(&filter, "10.0.0.1", Some("198.51.100.7, garbage, 10.0.0.2")),
+            ip("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn ipv4_mapped_addresses_are_matched_as_ipv4() {
+        let filter = ip_filter("trusted_proxies: [10.0.0.1]");
+        assert_eq!(
+            client_ip(&filter, "[::ffff:10.0.0.1]", Some("::ffff:198.51.100.7")),
+            ip("198.51.100.7")
+        );
+    }
+
+    #[test]
+    fn all_addresses_are_allowed_without_lists() {
+        let filter = ip_filter("{}");
+        assert!(filter.allows(Some(ip("192.0.2.1"))));
+        assert!(filter.allows(None));
+    }
+
+    #[test]
+    fn only_allowed_networks_are_allowed() {
+        let filter = ip_filter("allowed_ips: [192.0.2.0/24, 2001:db8::/32]");
+        assert!(filter.allows(Some(ip("192.0.2.1"))));
+        assert!(filter.allows(Some(ip("2001:db8::1"))));
+        assert!(!filter.allows(Some(ip("198.51.100.7"))));
+        assert!(!filter.allows(None));
+    }
+
+    #[test]
+    fn denied_networks_take_precedence_over_allowed_ones() {
+        let filter = ip_filter("allowed_ips: [192.0.2.0/24]\ndenied_ips: [192.0.2.128/25]");
+        assert!(filter.allows(Some(ip("192.0.2.1"))));
+        assert!(!filter.allows(Some(ip("192.0.2.200"))));
+
+        let denied_only = ip_filter("denied_ips: [192.0.2.0/24]");
+        assert!(!denied_only.allows(Some(ip("192.0.2.1"))));
+        assert!(denied_only.allows(Some(ip("198.51.100.7"))));
+    }
+}
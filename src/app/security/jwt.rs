@@ -0,0 +1,711 @@
+use super::error::AuthorizationError;
+use arc_swap::ArcSwap;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use log::{error, info, warn};
+use navdata::config::{JwtConfig, TokenScope};
+use navdata::messages::HTTP_USER_AGENT;
+use ring::signature::{self, RsaPublicKeyComponents, UnparsedPublicKey};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Minimum delay between two JWKS reloads triggered by unknown keys
+const JWKS_MIN_RELOAD: Duration = Duration::from_secs(60);
+const JWKS_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum duration of a JWKS download
+const JWKS_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+struct JwtHeader {
+    alg: String,
+    kid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JwkSetDocument {
+    keys: Vec<JwkDocument>,
+}
+
+#[derive(Deserialize)]
+struct JwkDocument {
+    kty: String,
+    kid: Option<String>,
+    alg: Option<String>,
+    #[serde(rename = "use")]
+    usage: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+    crv: Option<String>,
+    x: Option<String>,
+    y: Option<String>,
+}
+
+#[derive(Clone)]
+enum PublicKey {
+    Rsa {
+        n: Vec<u8>,
+        e: Vec<u8>,
+    },
+    /// Uncompressed P-256 point
+    P256(Vec<u8>),
+}
+
+impl PublicKey {
+    fn algorithm(&self) -> &'static str {
+        match self {
+            PublicKey::Rsa { .. } => "RS256",
+            PublicKey::P256(_) => "ES256",
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match self {
+            PublicKey::Rsa { n, e } => RsaPublicKeyComponents { n, e }
+                .verify(&signature::RSA_PKCS1_2048_8192_SHA256, message, signature)
+                .is_ok(),
+            PublicKey::P256(point) => {
+                UnparsedPublicKey::new(&signature::ECDSA_P256_SHA256_FIXED, point)
+                    .verify(message, signature)
+                    .is_ok()
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Jwk {
+    kid: Option<String>,
+    key: PublicKey,
+}
+
+impl TryFrom<JwkDocument> for Jwk {
+    type Error = String;
+
+    fn try_from(document: JwkDocument) -> Result<Jwk, String> {
+        if document
+            .usage
+            .as_deref()
+            .is_some_and(|usage| usage != "sig")
+        {
+            return Err("not a signature key".to_string());
+        }
+        let decode = |value: Option<String>, name: &str| {
+            let value = value.ok_or(format!("missing {}", name))?;
+            URL_SAFE_NO_PAD
+                .decode(value)
+                .map_err(|err| format!("invalid {} : {}", name, err))
+        };
+        let key = match (document.kty.as_str(), document.crv.as_deref()) {
+            ("RSA", _) => PublicKey::Rsa {
+                n: decode(document.n, "n")?,
+                e: decode(document.e, "e")?,
+            },
+            ("EC", Some("P-256")) => {
+                let mut point = vec![0x04];
+                point.extend(decode(document.x, "x")?);
+                point.extend(decode(document.y, "y")?);
+                PublicKey::P256(point)
+            }
+            (kty, crv) => {
+                return Err(format!(
+                    "unsupported key type {}{}",
+                    kty,
+                    crv.map(|crv| format!(" {}", crv)).unwrap_or_default()
+                ))
+            }
+        };
+        if let Some(alg) = document.alg.filter(|alg| alg != key.algorithm()) {
+            return Err(format!("unsupported algorithm {}", alg));
+        }
+        Ok(Jwk {
+            kid: document.kid,
+            key,
+        })
+    }
+}
+
+#[derive(Default)]
+struct KeySet {
+    keys: Vec<Jwk>,
+    /// Time of the last load attempt
+    loaded: Option<Instant>,
+}
+
+/// Keys of the JWKS, shared with the reloads running in the background
+struct Jwks {
+    path: Option<PathBuf>,
+    url: Option<String>,
+    keys: ArcSwap<KeySet>,
+    /// Set while a background reload is running
+    reloading: AtomicBool,
+}
+
+/// Validates RS256 and ES256 JWTs against the keys of a JWKS
+pub struct JwtValidator {
+    config: JwtConfig,
+    jwks: Arc<Jwks>,
+}
+
+impl JwtValidator {
+    pub fn new(config: &JwtConfig) -> JwtValidator {
+        JwtValidator {
+            config: config.clone(),
+            jwks: Arc::new(Jwks {
+                path: config.jwks_path.clone(),
+                url: config.jwks_url.clone(),
+                keys: ArcSwap::from_pointee(KeySet::default()),
+                reloading: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    /// Validates the JWT and checks that its claims grant the scope. Returns the
+    /// subject of the token
+    pub fn authorize(
+        &self,
+        token: Option<&str>,
+        scope: Option<TokenScope>,
+    ) -> Result<String, AuthorizationError> {
+        let token = token.ok_or(AuthorizationError::NoToken)?;
+        let claims = self.validate(token)?;
+        let subject = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown subject")
+            .to_string();
+        if let Some(scope) = scope.filter(|scope| !self.scopes(&claims).contains(scope)) {
+            return Err(AuthorizationError::MissingScope(scope));
+        }
+        Ok(subject)
+    }
+
+    /// Checks the signature, `exp`, `nbf`, `iss` and `aud`. Returns the claims
+    fn validate(&self, token: &str) -> Result<Value, AuthorizationError> {
+        let invalid = |reason: &str| AuthorizationError::InvalidJwt(reason.to_string());
+
+        let parts: Vec<&str> = token.split('.').collect();
+        let [header, payload, signature] = parts[..] else {
+            return Err(invalid("malformed token"));
+        };
+        let header: JwtHeader = decode_json(header).ok_or(invalid("malformed header"))?;
+        if header.alg != "RS256" && header.alg != "ES256" {
+            return Err(AuthorizationError::InvalidJwt(format!(
+                "unsupported algorithm {}",
+                header.alg
+            )));
+        }
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .map_err(|_| invalid("malformed signature"))?;
+        // The signature covers the encoded header and payload
+        let message = &token[..token.len() - parts[2].len() - 1];
+        let keys = self.keys(header.kid.as_deref());
+        let verified = keys
+            .keys
+            .iter()
+            .filter(|jwk| matches_kid(jwk, header.kid.as_deref()))
+            .filter(|jwk| jwk.key.algorithm() == header.alg)
+            .any(|jwk| jwk.key.verify(message.as_bytes(), &signature));
+        if !verified {
+            return Err(invalid("invalid signature"));
+        }
+
+        let claims: Value = decode_json(payload).ok_or(invalid("malformed payload"))?;
+        let now = Utc::now().timestamp();
+        let leeway = self.config.leeway as i64;
+        let exp = claims
+            .get("exp")
+            .and_then(Value::as_i64)
+            .ok_or(invalid("missing exp claim"))?;
+        if exp.saturating_add(leeway) < now {
+            return Err(AuthorizationError::Expired);
+        }
+        if claims
+            .get("nbf")
+            .and_then(Value::as_i64)
+            .is_some_and(|nbf| nbf.saturating_sub(leeway) > now)
+        {
+            return Err(invalid("token not valid yet"));
+        }
+        if claims.get("iss").and_then(Value::as_str) != Some(self.config.issuer.as_str()) {
+            return Err(invalid("unexpected issuer"));
+        }
+        let audience_matches = match claims.get("aud") {
+            Some(Value::String(audience)) => *audience == self.config.audience,
+            Some(Value::Array(audiences)) => audiences
+                .iter()
+                .any(|audience| audience.as_str() == Some(self.config.audience.as_str())),
+            _ => false,
+        };
+        if !audience_matches {
+            return Err(invalid("unexpected audience"));
+        }
+        Ok(claims)
+    }
+
+    /// Scopes granted by the values of the scopes claim
+    fn scopes(&self, claims: &Value) -> Vec<TokenScope> {
+        let values: Vec<&str> = match claims.get(&self.config.scopes_claim) {
+            Some(Value::String(values)) => values.split_whitespace().collect(),
+            Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        values
+            .into_iter()
+            .flat_map(|value| {
+                let mapped = self.config.scope_mapping.get(value).into_iter().flatten();
+                mapped.copied().chain(value.parse::<TokenScope>().ok())
+            })
+            .collect()
+    }
+
+    /// Current keys. A reload is started in the background if they are too old, or
+    /// if none matches the key id, requests keep using the current keys meanwhile
+    fn keys(&self, kid: Option<&str>) -> Arc<KeySet> {
+        let current = self.jwks.keys.load_full();
+        let age = current.loaded.map(|loaded| loaded.elapsed());
+        let stale = age.is_none_or(|age| age.as_secs() >= self.config.jwks_refresh);
+        let unknown = !current.keys.iter().any(|jwk| matches_kid(jwk, kid));
+        if stale || (unknown && age.is_none_or(|age| age >= JWKS_MIN_RELOAD)) {
+            self.jwks.clone().reload_in_background();
+        }
+        current
+    }
+
+    /// Loads the JWKS. Previous keys are kept if it fails
+    pub async fn reload(&self) {
+        self.jwks.reload().await
+    }
+}
+
+impl Jwks {
+    /// Starts a reload, unless one is already running
+    fn reload_in_background(self: Arc<Self>) {
+        if !self.reloading.swap(true, Ordering::AcqRel) {
+            actix_rt::spawn(async move {
+                self.reload().await;
+                self.reloading.store(false, Ordering::Release);
+            });
+        }
+    }
+
+    async fn reload(&self) {
+        let keys = match self.fetch().await {
+            Ok(keys) => {
+                info!("JWKS loaded, {} keys", keys.len());
+                keys
+            }
+            Err(err) => {
+                error!("Could not load the JWKS : {}", err);
+                self.keys.load().keys.clone()
+            }
+        };
+        self.keys.store(Arc::new(KeySet {
+            keys,
+            loaded: Some(Instant::now()),
+        }));
+    }
+
+    async fn fetch(&self) -> Result<Vec<Jwk>, Box<dyn Error>> {
+        let document = match (&self.path, &self.url) {
+            (Some(path), _) => std::fs::read_to_string(path)?,
+            (None, Some(url)) => {
+                let client = reqwest::Client::builder()
+                    .user_agent(HTTP_USER_AGENT)
+                    .connect_timeout(JWKS_CONNECT_TIMEOUT)
+                    .timeout(JWKS_TIMEOUT)
+                    .build()?;
+                let result = client.get(url).send().await?;
+                result.error_for_status()?.text().await?
+            }
+            (None, None) => return Err("No JWKS path nor URL set".into()),
+        };
+        let set: JwkSetDocument = serde_json::from_str(&document)?;
+        Ok(set
+            .keys
+            .into_iter()
+            .filter_map(|document| {
+                let kid = document.kid.clone().unwrap_or_default();
+                Jwk::try_from(document)
+                    .map_err(|reason| warn!("JWKS key {} ignored : {}", kid, reason))
+                    .ok()
+            })
+            .collect())
+    }
+}
+
+/// Keys without id match any token, and tokens without key id match any key
+fn matches_kid(jwk: &Jwk, kid: Option<&str>) -> bool {
+    match (jwk.kid.as_deref(), kid) {
+        (Some(jwk_kid), Some(kid)) => jwk_kid == kid,
+        _ => true,
+    }
+}
+
+fn decode_json<T: DeserializeOwned>(part: &str) -> Option<T> {
+    let bytes = URL_SAFE_NO_PAD.decode(part).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{
+        EcdsaKeyPair, KeyPair, RsaKeyPair, ECDSA_P256_SHA256_FIXED_SIGNING, RSA_PKCS1_SHA256,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::net::TcpListener;
+    use uuid::Uuid;
+
+    const ISSUER: &str = "https://sso.example.com";
+    const AUDIENCE: &str = "navdata";
+
+    /// Test only key, ring cannot generate RSA keys
+    const RSA_TEST_KEY: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/jwt/rsa-test-key.pk8"
+    ));
+
+    enum TestKey {
+        Rsa(RsaKeyPair),
+        P256(EcdsaKeyPair),
+    }
+
+    impl TestKey {
+        fn rsa() -> TestKey {
+            TestKey::Rsa(RsaKeyPair::from_pkcs8(RSA_TEST_KEY).unwrap())
+        }
+
+        fn p256() -> TestKey {
+            let rng = SystemRandom::new();
+            let pkcs8 =
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+            let key =
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng);
+            TestKey::P256(key.unwrap())
+        }
+
+        fn algorithm(&self) -> &'static str {
+            match self {
+                TestKey::Rsa(_) => "RS256",
+                TestKey::P256(_) => "ES256",
+            }
+        }
+
+        fn jwk(&self, kid: &str) -> Value {
+            match self {
+                TestKey::Rsa(key) => {
+                    let public = RsaPublicKeyComponents::<Vec<u8>>::from(key.public());
+                    json!({
+                        "kty": "RSA",
+                        "kid": kid,
+                        "n": URL_SAFE_NO_PAD.encode(public.n),
+                        "e": URL_SAFE_NO_PAD.encode(public.e),
+                    })
+                }
+                TestKey::P256(key) => {
+                    let point = key.public_key().as_ref();
+                    json!({
+                        "kty": "EC",
+                        "crv": "P-256",
+                        "kid": kid,
+                        "x": URL_SAFE_NO_PAD.encode(&point[1..33]),
+                        "y": URL_SAFE_NO_PAD.encode(&point[33..]),
+                    })
+                }
+            }
+        }
+
+        fn sign(&self, message: &[u8]) -> Vec<u8> {
+            let rng = SystemRandom::new();
+            match self {
+                TestKey::Rsa(key) => {
+                    let mut signature = vec![0; key.public().modulus_len()];
+                    key.sign(&RSA_PKCS1_SHA256, &rng, message, &mut signature)
+                        .unwrap();
+                    signature
+                }
+                TestKey::P256(key) => key.sign(&rng, message).unwrap().as_ref().to_vec(),
+            }
+        }
+
+        /// JWT signed with this key, whatever the algorithm of its header
+        fn token_with_algorithm(&self, algorithm: &str, kid: &str, claims: &Value) -> String {
+            let header = json!({ "alg": algorithm, "typ": "JWT", "kid": kid });
+            let message = format!(
+                "{}.{}",
+                URL_SAFE_NO_PAD.encode(header.to_string()),
+                URL_SAFE_NO_PAD.encode(claims.to_string())
+            );
+            let signature = URL_SAFE_NO_PAD.encode(self.sign(message.as_bytes()));
+            format!("{}.{}", message, signature)
+        }
+
+        fn token(&self, kid: &str, claims: &Value) -> String {
+            self.token_with_algorithm(self.algorithm(), kid, claims)
+        }
+    }
+
+    fn config() -> JwtConfig {
+        JwtConfig {
+            issuer: ISSUER.to_string(),
+            audience: AUDIENCE.to_string(),
+            scope_mapping: HashMap::from([(
+                "navdata:read".to_string(),
+                vec![TokenScope::READ_AIRPORTS, TokenScope::READ_NAVAIDS],
+            )]),
+            ..Default::default()
+        }
+    }
+
+    fn claims() -> Value {
+        json!({
+            "sub": "alice",
+            "iss": ISSUER,
+            "aud": AUDIENCE,
+            "exp": Utc::now().timestamp() + 600,
+            "scope": "navdata:read",
+        })
+    }
+
+    fn with(claim: &str, value: Value) -> Value {
+        let mut claims = claims();
+        claims[claim] = value;
+        claims
+    }
+
+    fn jwks(keys: &[(&str, &TestKey)]) -> String {
+        let keys: Vec<Value> = keys.iter().map(|(kid, key)| key.jwk(kid)).collect();
+        json!({ "keys": keys }).to_string()
+    }
+
+    fn jwks_file(keys: &[(&str, &TestKey)]) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("navdata-jwks-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, jwks(keys)).unwrap();
+        path
+    }
+
+    /// Validator loaded with the keys of a JWKS file
+    async fn validator(keys: &[(&str, &TestKey)]) -> JwtValidator {
+        let path = jwks_file(keys);
+        let validator = JwtValidator::new(&JwtConfig {
+            jwks_path: Some(path.clone()),
+            ..config()
+        });
+        validator.reload().await;
+        std::fs::remove_file(path).unwrap();
+        validator
+    }
+
+    fn rejection(result: Result<String, AuthorizationError>) -> AuthorizationError {
+        match result {
+            Ok(subject) => panic!("token of {} accepted", subject),
+            Err(err) => err,
+        }
+    }
+
+    fn reason(result: Result<String, AuthorizationError>) -> String {
+        match rejection(result) {
+            AuthorizationError::InvalidJwt(reason) => reason,
+            err => panic!("unexpected rejection : {}", err),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn rs256_and_es256_tokens_are_accepted() {
+        let (rsa, p256) = (TestKey::rsa(), TestKey::p256());
+        let validator = validator(&[("rsa", &rsa), ("ec", &p256)]).await;
+        for (kid, key) in [("rsa", &rsa), ("ec", &p256)] {
+            let token = key.token(kid, &claims());
+            let subject = validator.authorize(Some(&token), Some(TokenScope::READ_AIRPORTS));
+            assert_eq!(subject.unwrap(), "alice");
+        }
+    }
+
+    #[actix_rt::test]
+    async fn tokens_signed_by_another_key_are_rejected() {
+        let (trusted, other) = (TestKey::p256(), TestKey::p256());
+        let validator = validator(&[("ec", &trusted)]).await;
+        let token = other.token("ec", &claims());
+        assert_eq!(
+            reason(validator.authorize(Some(&token), None)),
+            "invalid signature"
+        );
+
+        // Claims changed after signing
+        let token = trusted.token("ec", &claims());
+        let [header, _, signature] = token.split('.').collect::<Vec<&str>>()[..] else {
+            unreachable!()
+        };
+        let payload = URL_SAFE_NO_PAD.encode(with("sub", json!("mallory")).to_string());
+        let token = format!("{}.{}.{}", header, payload, signature);
+        assert_eq!(
+            reason(validator.authorize(Some(&token), None)),
+            "invalid signature"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn the_algorithm_must_match_the_key() {
+        let (rsa, p256) = (TestKey::rsa(), TestKey::p256());
+        let validator = validator(&[("rsa", &rsa), ("ec", &p256)]).await;
+        let token = rsa.token_with_algorithm("ES256", "rsa", &claims());
+        assert_eq!(
+            reason(validator.authorize(Some(&token), None)),
+            "invalid signature"
+        );
+        let token = p256.token_with_algorithm("RS256", "ec", &claims());
+        assert_eq!(
+            reason(validator.authorize(Some(&token), None)),
+            "invalid signature"
+        );
+        for algorithm in ["HS256", "none"] {
+            let token = rsa.token_with_algorithm(algorithm, "rsa", &claims());
+            assert_eq!(
+                reason(validator.authorize(Some(&token), None)),
+                format!("unsupported algorithm {}", algorithm)
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn unknown_key_ids_are_rejected() {
+        let key = TestKey::p256();
+        let validator = validator(&[("ec", &key)]).await;
+        let token = key.token("rotated", &claims());
+        assert_eq!(
+            reason(validator.authorize(Some(&token), None)),
+            "invalid signature"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn time_claims_are_checked_with_the_leeway() {
+        let key = TestKey::p256();
+        let validator = validator(&[("ec", &key)]).await;
+        let now = Utc::now().timestamp();
+        let authorize = |claims: Value| validator.authorize(Some(&key.token("ec", &claims)), None);
+
+        assert!(authorize(with("exp", json!(now - 30))).is_ok());
+        assert!(matches!(
+            rejection(authorize(with("exp", json!(now - 90)))),
+            AuthorizationError::Expired
+        ));
+        let mut claims = claims();
+        claims.as_object_mut().unwrap().remove("exp");
+        assert_eq!(reason(authorize(claims)), "missing exp claim");
+
+        assert!(authorize(with("nbf", json!(now + 30))).is_ok());
+        assert_eq!(
+            reason(authorize(with("nbf", json!(now + 90)))),
+            "token not valid yet"
+        );
+
+        // Extreme values do not overflow
+        assert!(authorize(with("exp", json!(i64::MAX))).is_ok());
+        assert!(matches!(
+            rejection(authorize(with("exp", json!(i64::MIN)))),
+            AuthorizationError::Expired
+        ));
+        assert!(authorize(with("nbf", json!(i64::MIN))).is_ok());
+        assert_eq!(
+            reason(authorize(with("nbf", json!(i64::MAX)))),
+            "token not valid yet"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn issuer_and_audience_must_match() {
+        let key = TestKey::p256();
+        let validator = validator(&[("ec", &key)]).await;
+        let authorize = |claims: Value| validator.authorize(Some(&key.token("ec", &claims)), None);
+
+        assert_eq!(
+            reason(authorize(with("iss", json!("https://evil.example.com")))),
+            "unexpected issuer"
+        );
+        assert_eq!(
+            reason(authorize(with("aud", json!("another-api")))),
+            "unexpected audience"
+        );
+        assert!(authorize(with("aud", json!(["another-api", AUDIENCE]))).is_ok());
+        assert_eq!(
+            reason(authorize(with("aud", json!(["another-api"])))),
+            "unexpected audience"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn scopes_are_mapped_from_the_scopes_claim() {
+        let key = TestKey::p256();
+        let validator = validator(&[("ec", &key)]).await;
+        let authorize = |scopes: Value, scope: TokenScope| {
+            validator.authorize(Some(&key.token("ec", &with("scope", scopes))), Some(scope))
+        };
+
+        assert!(authorize(json!("openid navdata:read"), TokenScope::READ_NAVAIDS).is_ok());
+        assert!(authorize(json!(["READ_AIRPORTS"]), TokenScope::READ_AIRPORTS).is_ok());
+        assert!(matches!(
+            rejection(authorize(json!("navdata:read"), TokenScope::ADMIN)),
+            AuthorizationError::MissingScope(TokenScope::ADMIN)
+        ));
+        assert!(matches!(
+            rejection(authorize(json!("openid"), TokenScope::READ_AIRPORTS)),
+            AuthorizationError::MissingScope(TokenScope::READ_AIRPORTS)
+        ));
+    }
+
+    #[actix_rt::test]
+    async fn rotated_keys_are_picked_up_by_a_background_reload() {
+        let (old, new) = (TestKey::p256(), TestKey::p256());
+        let path = jwks_file(&[("old", &old)]);
+        let validator = JwtValidator::new(&JwtConfig {
+            jwks_path: Some(path.clone()),
+            jwks_refresh: 0,
+            ..config()
+        });
+        validator.reload().await;
+        std::fs::write(&path, jwks(&[("old", &old), ("new", &new)])).unwrap();
+
+        let token = new.token("new", &claims());
+        assert!(validator.authorize(Some(&token), None).is_err());
+        while validator.jwks.reloading.load(Ordering::Acquire) {
+            actix_rt::task::yield_now().await;
+        }
+        std::fs::remove_file(path).unwrap();
+        assert!(validator.authorize(Some(&token), None).is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn stale_keys_are_served_while_the_jwks_server_hangs() {
+        // Accepts connections but never answers
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let key = TestKey::p256();
+        let validator = JwtValidator::new(&JwtConfig {
+            jwks_url: Some(format!("http://{}/jwks", server.local_addr().unwrap())),
+            jwks_refresh: 0,
+            ..config()
+        });
+        let document: JwkDocument = serde_json::from_value(key.jwk("ec")).unwrap();
+        validator.jwks.keys.store(Arc::new(KeySet {
+            keys: vec![Jwk::try_from(document).unwrap()],
+            loaded: Some(Instant::now()),
+        }));
+
+        let started = Instant::now();
+        let token = key.token("ec", &claims());
+        assert!(validator.authorize(Some(&token), None).is_ok());
+        assert!(validator.authorize(Some(&token), None).is_ok());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(validator.jwks.reloading.load(Ordering::Acquire));
+    }
+}
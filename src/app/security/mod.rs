@@ -1,2 +1,59 @@
 pub mod error;
+pub mod ip_filter;
+pub mod jwt;
+pub mod reload;
 pub mod simple_token;
+pub mod token;
+
+use arc_swap::ArcSwap;
+use ip_filter::IpFilter;
+use jwt::JwtValidator;
+use navdata::config::{AuthMode, SecurityConfig};
+use token::TokenStore;
+
+/// Security settings currently enforced. Replaced as a whole when the config file changes
+pub type SharedSecurity = ArcSwap<Security>;
+
+pub struct Security {
+    pub ip_filter: IpFilter,
+    pub authenticator: Authenticator,
+}
+
+impl Security {
+    pub async fn new(config: &SecurityConfig) -> Security {
+        Security {
+            ip_filter: IpFilter::new(config),
+            authenticator: Authenticator::new(config).await,
+        }
+    }
+
+    /// Settings of a reloaded config. Tokens still configured keep their usage counters
+    pub async fn reloaded(&self, config: &SecurityConfig) -> Security {
+        let mut security = Security::new(config).await;
+        if let (Authenticator::Tokens(tokens), Authenticator::Tokens(previous)) =
+            (&mut security.authenticator, &self.authenticator)
+        {
+            tokens.keep_usage(previous);
+        }
+        security
+    }
+}
+
+/// Authentication of the requests, as selected by `security.mode`
+pub enum Authenticator {
+    Tokens(TokenStore),
+    Jwt(Box<JwtValidator>),
+}
+
+impl Authenticator {
+    pub async fn new(security: &SecurityConfig) -> Authenticator {
+        match security.mode {
+            AuthMode::TOKEN => Authenticator::Tokens(TokenStore::new(security)),
+            AuthMode::JWT => {
+                let validator = JwtValidator::new(&security.jwt);
+                validator.reload().await;
+                Authenticator::Jwt(Box::new(validator))
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use super::SharedSecurity;
+use actix_web::web;
+use log::{error, info};
+use navdata::config::{Config, ConfigError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::time::sleep;
+
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Reloads the security settings whenever the config file changes, so that tokens
+/// are added or revoked without restart. Other settings still require a restart
+pub async fn watch_config(path: PathBuf, security: web::Data<SharedSecurity>) {
+    info!("Watching {} for security changes", path.display());
+    let mut modified = modified_time(&path);
+    loop {
+        sleep(CONFIG_POLL_INTERVAL).await;
+        let current = modified_time(&path);
+        if current == modified {
+            continue;
+        }
+        modified = current;
+        reload_security(&path, &security).await;
+    }
+}
+
+/// Replaces the security settings with those of the config file, unless it cannot be loaded
+async fn reload_security(path: &Path, security: &SharedSecurity) {
+    match load_config(path) {
+        Ok(config) => {
+            let reloaded = security.load().reloaded(&config.security).await;
+            security.store(Arc::new(reloaded));
+            info!("Security settings reloaded from {}", path.display());
+        }
+        Err(err) => error!(
+            "Security settings not reloaded, the current ones are kept : {}",
+            err
+        ),
+    }
+}
+
+/// Config file and environment. Unlike at startup, the file is required: a missing
+/// or empty file is most likely being rewritten, and would disable authentication
+fn load_config(path: &Path) -> Result<Config, ConfigError> {
+    let empty = fs::read_to_string(path).is_ok_and(|content| content.trim().is_empty());
+    if empty {
+        return Err(ConfigError::File {
+            path: path.display().to_string(),
+            reason: "empty file".to_string(),
+        });
+    }
+    let mut config = Config::from_file(path)?;
+    config.apply_env()?;
+    config.validate()?;
+    Ok(config)
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::security::{Authenticator, Security};
+    use uuid::Uuid;
+
+    const TOKENS: &str = "security:\n  auth_tokens:\n    - token: aaaa\n";
+
+    /// True if requests without a token are rejected
+    fn enforced(security: &SharedSecurity) -> bool {
+        let security = security.load();
+        match &security.authenticator {
+            Authenticator::Tokens(tokens) => tokens
+                .authorize(None, None, None, &security.ip_filter)
+                .is_err(),
+            Authenticator::Jwt(_) => unreachable!(),
+        }
+    }
+
+    #[actix_rt::test]
+    async fn unreadable_config_files_keep_the_current_settings() {
+        let path = std::env::temp_dir().join(format!("navdata-config-{}.yaml", Uuid::new_v4()));
+        fs::write(&path, TOKENS).unwrap();
+        let config = load_config(&path).unwrap();
+        let security = SharedSecurity::from_pointee(Security::new(&config.security).await);
+        assert!(enforced(&security));
+
+        fs::remove_file(&path).unwrap();
+        reload_security(&path, &security).await;
+        assert!(enforced(&security));
+
+        for content in ["", "\n", "security:\n  auth_tokens: [\n"] {
+            fs::write(&path, content).unwrap();
+            reload_security(&path, &security).await;
+            assert!(enforced(&security), "{:?}", content);
+        }
+
+        fs::write(&path, "security:\n  auth_tokens: []\n").unwrap();
+        reload_security(&path, &security).await;
+        assert!(!enforced(&security));
+        fs::remove_file(&path).unwrap();
+    }
+}
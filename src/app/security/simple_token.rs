@@ -1,8 +1,10 @@
 use super::error::AuthorizationError;
-use crate::app::db::AppState;
-use crate::app::messages::TOKEN_COOKIE;
+use super::ip_filter::display_ip;
+use super::token::{request_token, required_scope};
+use super::{Authenticator, SharedSecurity};
+use actix_web::body::EitherBody;
 use actix_web::dev::{forward_ready, Service, ServiceResponse, Transform};
-use actix_web::{dev::ServiceRequest, Error};
+use actix_web::{dev::ServiceRequest, web, Error};
 use std::{
     future::{ready, Future, Ready},
     pin::Pin,
@@ -19,7 +21,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type InitError = ();
     type Transform = SimpleTokenMiddleware<S>;
@@ -49,7 +51,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Future = LocalBoxFuture<Result<Self::Response, Self::Error>>;
 
@@ -65,50 +67,54 @@ where
         let svc = self.service.clone();
 
         Box::pin(async move {
-            let _peer_addr = match req.peer_addr() {
-                Some(ip) => ip.ip().to_string(),
-                None => "Unknown".to_string(),
-            };
-
-            let conn_info = req.connection_info().clone();
-            let real_remote_addr = conn_info.realip_remote_addr().unwrap_or("unknown");
-
-            let app_data = req.app_data::<AppState>().unwrap();
-
-            let success = match app_data.config.security.auth_tokens.len() {
-                // If no token set
-                0 => true,
-                _ => {
-                    let token = match req.cookie(TOKEN_COOKIE) {
-                        Some(cookie) => cookie.value().to_owned(),
-                        None => {
-                            return Err(Error::from(AuthorizationError::NoToken).into());
-                        }
-                    };
-                    app_data.config.security.auth_tokens.contains(&token)
+            // Settings at the time of the request, even if reloaded meanwhile
+            let security = req
+                .app_data::<web::Data<SharedSecurity>>()
+                .unwrap()
+                .load_full();
+            let ip_filter = &security.ip_filter;
+            let client_ip = ip_filter.client_ip(&req);
+            let real_remote_addr = display_ip(client_ip);
+
+            let token = request_token(&req);
+            // Admin routes always require an admin token
+            let scope = required_scope(req.path());
+
+            let authorization = match &security.authenticator {
+                Authenticator::Tokens(tokens) => tokens
+                    .authorize(token.as_deref(), scope, client_ip, ip_filter)
+                    .map(|stored| stored.map(|stored| stored.name.clone())),
+                Authenticator::Jwt(_) if !ip_filter.allows(client_ip) => {
+                    Err(AuthorizationError::AddressDenied(real_remote_addr.clone()))
+                }
+                Authenticator::Jwt(validator) => {
+                    validator.authorize(token.as_deref(), scope).map(Some)
                 }
             };
-
-            match success {
-                true => (),
-                false => {
+            let token_name = match authorization {
+                Ok(name) => name,
+                Err(err) => {
                     log::error!(
-                        "Unauthorized access attempt for ip {} for {}",
+                        "Unauthorized access attempt for ip {} for {} : {}",
                         real_remote_addr,
-                        req.path()
+                        req.path(),
+                        err
                     );
-                    return Err(Error::from(AuthorizationError::InvalidToken).into());
+                    // Answered rather than failed, so that outer middlewares such as CORS
+                    // still process the response
+                    return Ok(req.error_response(err).map_into_right_body());
                 }
-            }
+            };
             log::info!(
-                "Authorized access attempt for ip {} for {}",
+                "Authorized access attempt for ip {} for {} with token {}",
                 real_remote_addr,
-                req.path()
+                req.path(),
+                token_name.as_deref().unwrap_or("none")
             );
 
             let res = svc.call(req).await?;
 
-            Ok(res)
+            Ok(res.map_into_left_body())
         })
     }
 }
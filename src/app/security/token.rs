@@ -0,0 +1,465 @@
+use super::error::AuthorizationError;
+use super::ip_filter::{display_ip, ip_allowed, IpFilter};
+use actix_web::dev::ServiceRequest;
+use actix_web::http::header::AUTHORIZATION;
+use chrono::{NaiveDate, Utc};
+use ipnet::IpNet;
+use navdata::config::{SecurityConfig, TokenConfig, TokenScope, TOKEN_HASH_PREFIX};
+use navdata::messages::{API_KEY_HEADER, ERROR_TOKEN_USAGE_ACCESS, TOKEN_COOKIE};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+const BEARER_PREFIX: &str = "Bearer ";
+const QUOTA_WINDOW: Duration = Duration::from_secs(60);
+
+/// Token of the request, from the `Authorization: Bearer` header, the `X-API-Key`
+/// header or the auth cookie, in this order
+pub fn request_token(req: &ServiceRequest) -> Option<String> {
+    let header = |name| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+    };
+    let bearer = header(AUTHORIZATION.as_str()).and_then(|value| {
+        // The scheme is case insensitive
+        let prefix = value.get(..BEARER_PREFIX.len())?;
+        prefix
+            .eq_ignore_ascii_case(BEARER_PREFIX)
+            .then(|| value[BEARER_PREFIX.len()..].trim())
+    });
+    bearer
+        .or_else(|| header(API_KEY_HEADER))
+        .filter(|token| !token.is_empty())
+        .map(str::to_owned)
+        .or_else(|| {
+            req.cookie(TOKEN_COOKIE)
+                .map(|cookie| cookie.value().to_owned())
+        })
+}
+
+/// Scope required by a route. Routes without scope only require a valid token
+pub fn required_scope(path: &str) -> Option<TokenScope> {
+    let under = |root: &str| {
+        path.strip_prefix(root)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    };
+    if under("/admin") {
+        Some(TokenScope::ADMIN)
+    } else if under("/airport") {
+        Some(TokenScope::READ_AIRPORTS)
+    } else if under("/navaid") {
+        Some(TokenScope::READ_NAVAIDS)
+    } else {
+        None
+    }
+}
+
+/// Configured tokens, with their usage since the server started
+pub struct TokenStore {
+    tokens: Vec<StoredToken>,
+    /// Token verification is bypassed, except for admin routes, if no auth token is set
+    required: bool,
+}
+
+pub struct StoredToken {
+    pub name: String,
+    /// Hexadecimal SHA-256 hash of the token
+    hash: String,
+    scopes: Vec<TokenScope>,
+    expires: Option<NaiveDate>,
+    requests_per_minute: Option<u32>,
+    allowed_ips: Vec<IpNet>,
+    denied_ips: Vec<IpNet>,
+    usage: Mutex<TokenUsage>,
+}
+
+#[derive(Clone, Default)]
+struct TokenUsage {
+    /// Accepted requests
+    requests: u64,
+    /// Requests rejected because of the quota
+    throttled: u64,
+    last_request: Option<i64>,
+    window_start: Option<Instant>,
+    window_requests: u32,
+}
+
+/// Usage counters of a token, as shown to admins
+#[derive(Serialize)]
+pub struct TokenReport {
+    name: String,
+    scopes: Vec<TokenScope>,
+    expires: Option<NaiveDate>,
+    requests_per_minute: Option<u32>,
+    requests: u64,
+    throttled: u64,
+    /// Unix timestamp of the last request, throttled or not
+    last_request: Option<i64>,
+}
+
+impl TokenStore {
+    pub fn new(security: &SecurityConfig) -> TokenStore {
+        let admin_scopes = [
+            TokenScope::READ_AIRPORTS,
+            TokenScope::READ_NAVAIDS,
+            TokenScope::ADMIN,
+        ];
+        let tokens = security
+            .auth_tokens
+            .iter()
+            .map(|config| StoredToken::new(config, config.scopes.clone()))
+            // Last, so that they take precedence if also listed as auth tokens
+            .chain(
+                security
+                    .admin_tokens
+                    .iter()
+                    .map(|config| StoredToken::new(config, admin_scopes.to_vec())),
+            )
+            .collect();
+        TokenStore {
+            tokens,
+            required: !security.auth_tokens.is_empty(),
+        }
+    }
+
+    /// Checks that the client address is allowed, that the token grants the scope,
+    /// and counts the request against its quota. Returns the matching token, if any.
+    /// Tokens with their own address lists are checked against them instead of the
+    /// global ones
+    pub fn authorize(
+        &self,
+        token: Option<&str>,
+        scope: Option<TokenScope>,
+        client_ip: Option<IpAddr>,
+        ip_filter: &IpFilter,
+    ) -> Result<Option<&StoredToken>, AuthorizationError> {
+        let stored = token.and_then(|token| self.find(token));
+        match stored.filter(|stored| stored.has_address_lists()) {
+            Some(stored) => {
+                if !ip_allowed(client_ip, &stored.allowed_ips, &stored.denied_ips) {
+                    return Err(AuthorizationError::AddressDenied(format!(
+                        "{} with token {}",
+                        display_ip(client_ip),
+                        stored.name
+                    )));
+                }
+            }
+            None => {
+                if !ip_filter.allows(client_ip) {
+                    return Err(AuthorizationError::AddressDenied(display_ip(client_ip)));
+                }
+            }
+        }
+
+        let required = self.required || scope == Some(TokenScope::ADMIN);
+        let stored = match (token, stored) {
+            (_, Some(stored)) => stored,
+            (None, None) if required => return Err(AuthorizationError::NoToken),
+            (Some(_), None) if required => return Err(AuthorizationError::InvalidToken),
+            (_, None) => return Ok(None),
+        };
+        if stored
+            .expires
+            .is_some_and(|expires| Utc::now().date_naive() > expires)
+        {
+            return Err(AuthorizationError::Expired);
+        }
+        if let Some(scope) = scope.filter(|scope| !stored.scopes.contains(scope)) {
+            return Err(AuthorizationError::MissingScope(scope));
+        }
+        stored.count_request(Instant::now())?;
+        Ok(Some(stored))
+    }
+
+    /// Finds the token in constant time, whatever the matching token and whether
+    /// tokens are configured in plaintext or hashed
+    fn find(&self, token: &str) -> Option<&StoredToken> {
+        let hash = sha256_hex(token);
+        let mut found = Choice::from(0);
+        let mut index = 0u64;
+        for (i, stored) in self.tokens.iter().enumerate() {
+            let matches = hash.as_bytes().ct_eq(stored.hash.as_bytes());
+            index.conditional_assign(&(i as u64), matches);
+            found |= matches;
+        }
+        bool::from(found).then(|| &self.tokens[index as usize])
+    }
+
+    /// Takes over the usage of the same tokens in another store
+    pub fn keep_usage(&mut self, previous: &TokenStore) {
+        for stored in &mut self.tokens {
+            if let Some(usage) = previous
+                .tokens
+                .iter()
+                .find(|previous| previous.hash == stored.hash)
+                .map(|previous| previous.usage.lock().expect(ERROR_TOKEN_USAGE_ACCESS))
+            {
+                *stored.usage.get_mut().expect(ERROR_TOKEN_USAGE_ACCESS) = usage.clone();
+            }
+        }
+    }
+
+    pub fn reports(&self) -> Vec<TokenReport> {
+        self.tokens.iter().map(StoredToken::report).collect()
+    }
+}
+
+impl StoredToken {
+    fn new(config: &TokenConfig, scopes: Vec<TokenScope>) -> StoredToken {
+        let hash = match config.token.strip_prefix(TOKEN_HASH_PREFIX) {
+            Some(hash) => hash.to_ascii_lowercase(),
+            None => sha256_hex(&config.token),
+        };
+        StoredToken {
+            // The start of the hash identifies unnamed tokens without revealing them
+            name: config
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("{}{}", TOKEN_HASH_PREFIX, &hash[..8])),
+            hash,
+            scopes,
+            expires: config.expires,
+            requests_per_minute: config.requests_per_minute,
+            allowed_ips: config.allowed_ips.clone(),
+            denied_ips: config.denied_ips.clone(),
+            usage: Mutex::new(TokenUsage::default()),
+        }
+    }
+
+    fn has_address_lists(&self) -> bool {
+        !self.allowed_ips.is_empty() || !self.denied_ips.is_empty()
+    }
+
+    /// Counts a request in the current one minute window. Fails with the number of
+    /// seconds until the next window if the quota is reached
+    fn count_request(&self, now: Instant) -> Result<(), AuthorizationError> {
+        let mut usage = self.usage.lock().expect(ERROR_TOKEN_USAGE_ACCESS);
+        usage.last_request = Some(Utc::now().timestamp());
+        if let Some(limit) = self.requests_per_minute {
+            let window_start = match usage.window_start {
+                Some(start) if now.duration_since(start) < QUOTA_WINDOW => start,
+                _ => {
+                    usage.window_requests = 0;
+                    *usage.window_start.insert(now)
+                }
+            };
+            if usage.window_requests >= limit {
+                usage.throttled += 1;
+                let remaining = QUOTA_WINDOW.saturating_sub(now.duration_since(window_start));
+                return Err(AuthorizationError::QuotaExceeded {
+                    limit,
+                    retry_after: remaining.as_secs() + 1,
+                });
+            }
+            usage.window_requests += 1;
+        }
+        usage.requests += 1;
+        Ok(())
+    }
+
+    fn report(&self) -> TokenReport {
+        let usage = self.usage.lock().expect(ERROR_TOKEN_USAGE_ACCESS).clone();
+        TokenReport {
+            name: self.name.clone(),
+            scopes: self.scopes.clone(),
+            expires: self.expires,
+            requests_per_minute: self.requests_per_minute,
+            requests: usage.requests,
+            throttled: usage.throttled,
+            last_request: usage.last_request,
+        }
+    }
+}
+
+fn sha256_hex(value: &str) -> String {
+    format!("{:x}", Sha256::digest(value.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::error::ApiError;
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+
+    fn open() -> IpFilter {
+        IpFilter::new(&SecurityConfig::default())
+    }
+
+    fn store(yaml: &str) -> TokenStore {
+        TokenStore::new(&serde_yaml::from_str::<SecurityConfig>(yaml).unwrap())
+    }
+
+    fn rejection(result: Result<Option<&StoredToken>, AuthorizationError>) -> AuthorizationError {
+        match result {
+            Ok(_) => panic!("request accepted"),
+            Err(err) => err,
+        }
+    }
+
+    fn status(err: AuthorizationError) -> StatusCode {
+        ApiError::from(&err).status_code()
+    }
+
+    fn expiring(days: i64) -> TokenStore {
+        let expires = Utc::now().date_naive() + chrono::Duration::days(days);
+        store(&format!(
+            "auth_tokens:\n  - token: aaaa\n    expires: {}\n",
+            expires
+        ))
+    }
+
+    #[test]
+    fn tokens_are_valid_until_the_end_of_their_last_day() {
+        assert!(expiring(1)
+            .authorize(Some("aaaa"), None, None, &open())
+            .is_ok());
+        assert!(expiring(0)
+            .authorize(Some("aaaa"), None, None, &open())
+            .is_ok());
+    }
+
+    #[test]
+    fn expired_tokens_are_rejected() {
+        let err = rejection(expiring(-1).authorize(Some("aaaa"), None, None, &open()));
+        assert!(matches!(err, AuthorizationError::Expired));
+        assert_eq!(status(err), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn tokens_only_grant_their_scopes() {
+        let store = store("auth_tokens:\n  - token: aaaa\n    scopes: [READ_NAVAIDS]\n");
+        let navaids = store.authorize(Some("aaaa"), Some(TokenScope::READ_NAVAIDS), None, &open());
+        assert!(navaids.is_ok());
+
+        let err = rejection(store.authorize(
+            Some("aaaa"),
+            Some(TokenScope::READ_AIRPORTS),
+            None,
+            &open(),
+        ));
+        assert!(matches!(
+            err,
+            AuthorizationError::MissingScope(TokenScope::READ_AIRPORTS)
+        ));
+        assert_eq!(status(err), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn admin_tokens_grant_every_scope() {
+        let store = store("auth_tokens: [aaaa]\nadmin_tokens: [bbbb]\n");
+        for scope in [
+            TokenScope::READ_AIRPORTS,
+            TokenScope::READ_NAVAIDS,
+            TokenScope::ADMIN,
+        ] {
+            assert!(store
+                .authorize(Some("bbbb"), Some(scope), None, &open())
+                .is_ok());
+        }
+        let admin = store.authorize(Some("aaaa"), Some(TokenScope::ADMIN), None, &open());
+        assert!(matches!(admin, Err(AuthorizationError::MissingScope(_))));
+    }
+
+    #[test]
+    fn quota_exhaustion_is_answered_with_429() {
+        let store = store("auth_tokens:\n  - token: aaaa\n    requests_per_minute: 2\n");
+        for _ in 0..2 {
+            assert!(store.authorize(Some("aaaa"), None, None, &open()).is_ok());
+        }
+        let err = rejection(store.authorize(Some("aaaa"), None, None, &open()));
+        let AuthorizationError::QuotaExceeded { limit, retry_after } = err else {
+            panic!("quota not enforced");
+        };
+        assert_eq!(limit, 2);
+        assert!((1..=60).contains(&retry_after));
+        assert_eq!(status(err), StatusCode::TOO_MANY_REQUESTS);
+
+        let report = &store.reports()[0];
+        assert_eq!((report.requests, report.throttled), (2, 1));
+    }
+
+    #[test]
+    fn quota_resets_on_the_next_minute_window() {
+        let store = store("auth_tokens:\n  - token: aaaa\n    requests_per_minute: 1\n");
+        let token = &store.tokens[0];
+        let start = Instant::now();
+        assert!(token.count_request(start).is_ok());
+        let throttled = token.count_request(start + Duration::from_secs(59));
+        assert!(matches!(
+            throttled,
+            Err(AuthorizationError::QuotaExceeded { retry_after: 2, .. })
+        ));
+        assert!(token.count_request(start + QUOTA_WINDOW).is_ok());
+        assert!(token
+            .count_request(start + QUOTA_WINDOW + Duration::from_secs(1))
+            .is_err());
+    }
+
+    fn ip_filter(yaml: &str) -> IpFilter {
+        IpFilter::new(&serde_yaml::from_str::<SecurityConfig>(yaml).unwrap())
+    }
+
+    #[test]
+    fn global_address_lists_apply_to_tokens_without_their_own() {
+        let filter = ip_filter("allowed_ips: [192.0.2.0/24]");
+        let store = store("auth_tokens: [aaaa]\n");
+        let allowed = "192.0.2.1".parse().ok();
+        let denied = "198.51.100.7".parse().ok();
+        assert!(store
+            .authorize(Some("aaaa"), None, allowed, &filter)
+            .is_ok());
+        let err = rejection(store.authorize(Some("aaaa"), None, denied, &filter));
+        assert!(matches!(err, AuthorizationError::AddressDenied(_)));
+        assert_eq!(status(err), StatusCode::FORBIDDEN);
+        // Denied addresses are answered before their token is checked
+        let err = rejection(store.authorize(Some("zzzz"), None, denied, &filter));
+        assert!(matches!(err, AuthorizationError::AddressDenied(_)));
+    }
+
+    #[test]
+    fn token_address_lists_override_the_global_ones() {
+        let filter = ip_filter("allowed_ips: [192.0.2.0/24]\ndenied_ips: [198.51.100.0/24]");
+        let store = store(
+            "auth_tokens:
+  - aaaa
+  - token: bbbb
+    allowed_ips: [198.51.100.0/24]
+  - token: cccc
+    denied_ips: [192.0.2.128/25]
+",
+        );
+        let globally_allowed = "192.0.2.1".parse().ok();
+        let globally_denied = "198.51.100.7".parse().ok();
+        let elsewhere = "203.0.113.1".parse().ok();
+
+        // Allowed from a globally denied network, and only from there
+        let bbbb = |ip| store.authorize(Some("bbbb"), None, ip, &filter);
+        assert!(bbbb(globally_denied).is_ok());
+        assert!(bbbb(globally_allowed).is_err());
+        assert!(bbbb(elsewhere).is_err());
+
+        // With a deny list only, every other address is allowed
+        let cccc = |ip| store.authorize(Some("cccc"), None, ip, &filter);
+        assert!(cccc(globally_allowed).is_ok());
+        assert!(cccc(elsewhere).is_ok());
+        assert!(cccc("192.0.2.200".parse().ok()).is_err());
+
+        // Other tokens keep the global lists
+        let aaaa = store.authorize(Some("aaaa"), None, globally_denied, &filter);
+        assert!(aaaa.is_err());
+    }
+
+    #[test]
+    fn tokens_without_quota_are_never_throttled() {
+        let store = store("auth_tokens: [aaaa]\n");
+        for _ in 0..1000 {
+            assert!(store.authorize(Some("aaaa"), None, None, &open()).is_ok());
+        }
+    }
+}
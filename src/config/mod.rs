@@ -0,0 +1,902 @@
+use chrono::NaiveDate;
+use derive_more::Display;
+use ipnet::IpNet;
+use log::info;
+use serde::de::{self, DeserializeOwned, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use std::env::{self, VarError};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::db::schedule::{RefreshMode, Schedule, StartupMode};
+use super::db::source::{DEFAULT_API_URL, DEFAULT_BRANCH, DEFAULT_RAW_URL};
+use super::db::BackendType;
+
+/// Prefix of the environment variables overriding the config file
+pub const ENV_PREFIX: &str = "NAVDATA_";
+
+/// Marks the security tokens stored as the hexadecimal SHA-256 hash of the token
+pub const TOKEN_HASH_PREFIX: &str = "sha256:";
+
+/// Shortest delay between two scheduled reloads of the JWKS, in seconds
+const JWKS_MIN_REFRESH: u64 = 60;
+
+#[derive(Debug, Display)]
+pub enum ConfigError {
+    #[display(fmt = "Could not read config file {} : {}", path, reason)]
+    File { path: String, reason: String },
+    #[display(fmt = "Invalid value for {} : {}", name, reason)]
+    Environment { name: String, reason: String },
+    #[display(fmt = "Invalid configuration : {}", _0)]
+    Invalid(String),
+}
+
+impl Error for ConfigError {}
+
+/// Server configuration. Each value comes from the first of the command line flags,
+/// the `NAVDATA_*` environment variables, the YAML file and the defaults
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub http: HttpConfig,
+    pub security: SecurityConfig,
+    pub database: DatabaseConfig,
+}
+
+impl Config {
+    /// Defaults, overridden by the YAML file if it exists, then by the environment
+    pub fn load(path: Option<&Path>) -> Result<Config, ConfigError> {
+        let mut config = match path {
+            Some(path) if path.exists() => Config::from_file(path)?,
+            Some(path) => {
+                info!(
+                    "Config file {} not found, using the defaults and the environment",
+                    path.display()
+                );
+                Config::default()
+            }
+            None => Config::default(),
+        };
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    /// Reads a YAML config file. Missing sections and keys keep their default value
+    pub fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let file_error = |reason: String| ConfigError::File {
+            path: path.display().to_string(),
+            reason,
+        };
+        let file = File::open(path).map_err(|err| file_error(err.to_string()))?;
+        serde_yaml::from_reader(file).map_err(|err| file_error(err.to_string()))
+    }
+
+    /// Overrides the values set in `NAVDATA_*` environment variables. Numbers and
+    /// enumerations are written as in the YAML file, lists are comma separated
+    pub fn apply_env(&mut self) -> Result<(), ConfigError> {
+        if let Some(host) = env_var("HOST")? {
+            self.http.host = host;
+        }
+        if let Some(port) = env_value("PORT")? {
+            self.http.port = port;
+        }
+        if let Some(origins) = env_list("CORS_ORIGINS")? {
+            self.http.cors.allowed_origins = origins;
+        }
+        if let Some(tokens) = env_list("TOKEN_LIST")? {
+            self.security.auth_tokens = tokens.into_iter().map(TokenConfig::from).collect();
+        }
+        if let Some(tokens) = env_list("ADMIN_TOKENS")? {
+            self.security.admin_tokens = tokens.into_iter().map(TokenConfig::from).collect();
+        }
+        if let Some(networks) = env_networks("ALLOWED_IPS")? {
+            self.security.allowed_ips = networks;
+        }
+        if let Some(networks) = env_networks("DENIED_IPS")? {
+            self.security.denied_ips = networks;
+        }
+        if let Some(networks) = env_networks("TRUSTED_PROXIES")? {
+            self.security.trusted_proxies = networks;
+        }
+        if let Some(mode) = env_value("AUTH_MODE")? {
+            self.security.mode = mode;
+        }
+        if let Some(path) = env_var("JWKS_PATH")? {
+            self.security.jwt.jwks_path = Some(PathBuf::from(path));
+        }
+        if let Some(url) = env_var("JWKS_URL")? {
+            self.security.jwt.jwks_url = Some(url);
+        }
+        if let Some(issuer) = env_var("JWT_ISSUER")? {
+            self.security.jwt.issuer = issuer;
+        }
+        if let Some(audience) = env_var("JWT_AUDIENCE")? {
+            self.security.jwt.audience = audience;
+        }
+        // Shortcut for MongoDB deployments, overridden by the generic variables
+        if let Some(url) = env_var("MONGODB_URL")? {
+            self.database.backend = BackendType::MONGODB;
+            self.database.path = Some(url);
+        }
+        if let Some(backend) = env_value("BACKEND")? {
+            self.database.backend = backend;
+        }
+        if let Some(path) = env_var("DATABASE_PATH")? {
+            self.database.path = Some(path);
+        }
+        if let Some(path) = env_var("DATA_PATH")? {
+            self.database.data_source.path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = env_var("SNAPSHOT")? {
+            self.database.snapshot = Some(PathBuf::from(path));
+        }
+        if let Some(mode) = env_value("REFRESH_MODE")? {
+            self.database.refresh.mode = mode;
+        }
+        if let Some(interval) = env_value("REFRESH_INTERVAL")? {
+            self.database.refresh.interval = interval;
+        }
+        if let Some(cron) = env_var("REFRESH_CRON")? {
+            self.database.refresh.cron = Some(cron);
+        }
+        if let Some(startup) = env_value("REFRESH_STARTUP")? {
+            self.database.refresh.startup = startup;
+        }
+        Ok(())
+    }
+
+    /// Checks the consistency of the configuration, returning the first error found
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |reason: &str| Err(ConfigError::Invalid(reason.to_string()));
+
+        if self.http.host.is_empty() {
+            return invalid("http.host must not be empty");
+        }
+        if self.http.port == 0 {
+            return invalid("http.port must be between 1 and 65535");
+        }
+        let cors = &self.http.cors;
+        if let Some(origin) = cors.allowed_origins.iter().find(|origin| {
+            let host = origin
+                .strip_prefix("https://")
+                .or_else(|| origin.strip_prefix("http://"));
+            *origin != "*"
+                && host.is_none_or(|host| {
+                    host.is_empty() || host.contains(|c: char| c == '/' || c.is_whitespace())
+                })
+        }) {
+            return Err(ConfigError::Invalid(format!(
+                "http.cors.allowed_origins : {} must be * or scheme://host[:port], without path",
+                origin
+            )));
+        }
+        if cors.allow_credentials && cors.allowed_origins.iter().any(|origin| origin == "*") {
+            return invalid("http.cors.allow_credentials is not allowed with the * origin");
+        }
+        let is_token = |value: &String| {
+            !value.is_empty()
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+        if let Some(name) = cors
+            .allowed_methods
+            .iter()
+            .chain(&cors.allowed_headers)
+            .find(|name| !is_token(name))
+        {
+            return Err(ConfigError::Invalid(format!(
+                "http.cors : {} is not a valid method or header name",
+                name
+            )));
+        }
+        let security = &self.security;
+        let tokens = || security.auth_tokens.iter().chain(&security.admin_tokens);
+        if tokens().any(|token| token.token.is_empty()) {
+            return invalid("security tokens must not be empty");
+        }
+        if tokens().any(|token| token.requests_per_minute == Some(0)) {
+            return invalid("security token requests_per_minute must be at least 1");
+        }
+        if let Some(token) = tokens()
+            .filter_map(|token| token.token.strip_prefix(TOKEN_HASH_PREFIX))
+            .find(|hash| hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()))
+        {
+            return Err(ConfigError::Invalid(format!(
+                "security token hash {}{} must be 64 hexadecimal characters",
+                TOKEN_HASH_PREFIX, token
+            )));
+        }
+        if let AuthMode::JWT = security.mode {
+            let jwt = &security.jwt;
+            match (&jwt.jwks_path, &jwt.jwks_url) {
+                (Some(_), Some(_)) | (None, None) => {
+                    return invalid(
+                        "exactly one of security.jwt.jwks_path and security.jwt.jwks_url must be set in JWT mode",
+                    );
+                }
+                (Some(path), None) if !path.exists() => {
+                    return Err(ConfigError::Invalid(format!(
+                        "security.jwt.jwks_path {} does not exist",
+                        path.display()
+                    )));
+                }
+                (None, Some(url))
+                    if !url.starts_with("https://") && !url.starts_with("http://") =>
+                {
+                    return invalid("security.jwt.jwks_url must be an http(s) URL");
+                }
+                _ => (),
+            }
+            if jwt.jwks_refresh < JWKS_MIN_REFRESH {
+                return Err(ConfigError::Invalid(format!(
+                    "security.jwt.jwks_refresh must be at least {} seconds",
+                    JWKS_MIN_REFRESH
+                )));
+            }
+            if jwt.issuer.is_empty() || jwt.audience.is_empty() {
+                return invalid(
+                    "security.jwt.issuer and security.jwt.audience must be set in JWT mode",
+                );
+            }
+        }
+
+        let database = &self.database;
+        let path = database.path.as_deref().unwrap_or_default();
+        match database.backend {
+            BackendType::MONGODB
+                if !path.starts_with("mongodb://") && !path.starts_with("mongodb+srv://") =>
+            {
+                return invalid("database.path must be a mongodb:// URL with the MONGODB backend");
+            }
+            BackendType::POSTGRES
+                if !path.starts_with("postgres://") && !path.starts_with("postgresql://") =>
+            {
+                return invalid(
+                    "database.path must be a postgresql:// URL with the POSTGRES backend",
+                );
+            }
+            _ => (),
+        }
+        if let Some(path) = &database.data_source.path {
+            if !path.exists() {
+                return Err(ConfigError::Invalid(format!(
+                    "database.data_source.path {} does not exist",
+                    path.display()
+                )));
+            }
+        }
+        if let Some(path) = &database.snapshot {
+            if !path.is_file() {
+                return Err(ConfigError::Invalid(format!(
+                    "database.snapshot {} does not exist",
+                    path.display()
+                )));
+            }
+        }
+        Schedule::try_from(&database.refresh)
+            .map_err(|err| ConfigError::Invalid(format!("database.refresh : {}", err)))?;
+        Ok(())
+    }
+}
+
+fn env_error(name: &str, reason: String) -> ConfigError {
+    ConfigError::Environment {
+        name: format!("{}{}", ENV_PREFIX, name),
+        reason,
+    }
+}
+
+/// Raw value of a `NAVDATA_*` environment variable. Empty variables are ignored
+fn env_var(name: &str) -> Result<Option<String>, ConfigError> {
+    match env::var(format!("{}{}", ENV_PREFIX, name)) {
+        Ok(value) if value.trim().is_empty() => Ok(None),
+        Ok(value) => Ok(Some(value.trim().to_string())),
+        Err(VarError::NotPresent) => Ok(None),
+        Err(err) => Err(env_error(name, err.to_string())),
+    }
+}
+
+/// Value of a `NAVDATA_*` environment variable, parsed as a YAML scalar
+fn env_value<T: DeserializeOwned>(name: &str) -> Result<Option<T>, ConfigError> {
+    env_var(name)?
+        .map(|value| serde_yaml::from_str(&value))
+        .transpose()
+        .map_err(|err| env_error(name, err.to_string()))
+}
+
+/// Comma separated list held by a `NAVDATA_*` environment variable
+fn env_list(name: &str) -> Result<Option<Vec<String>>, ConfigError> {
+    Ok(env_var(name)?.map(|list| {
+        list.split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }))
+}
+
+/// Comma separated list of networks
+fn env_networks(name: &str) -> Result<Option<Vec<IpNet>>, ConfigError> {
+    let Some(list) = env_list(name)? else {
+        return Ok(None);
+    };
+    list.iter()
+        .map(|network| parse_network(network))
+        .collect::<Result<Vec<IpNet>, String>>()
+        .map(Some)
+        .map_err(|reason| ConfigError::Environment {
+            name: format!("{}{}", ENV_PREFIX, name),
+            reason,
+        })
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub host: String,
+    pub port: u16,
+    pub cors: CorsConfig,
+}
+
+/// Cross-origin requests from browsers. None are allowed by default
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Origins allowed to call the API, such as `https://example.com`, or `*` for any
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    /// Request headers allowed in addition to the CORS-safelisted ones
+    pub allowed_headers: Vec<String>,
+    /// Seconds browsers may cache the answer to a preflight request
+    pub max_age: usize,
+    /// Lets browsers send the auth cookie. Not allowed with the `*` origin
+    pub allow_credentials: bool,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        CorsConfig {
+            allowed_origins: Vec::new(),
+            allowed_methods: vec!["GET".to_string()],
+            allowed_headers: vec![
+                "Authorization".to_string(),
+                "X-API-Key".to_string(),
+                "If-None-Match".to_string(),
+            ],
+            max_age: 3600,
+            allow_credentials: false,
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            cors: CorsConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct SecurityConfig {
+    /// Tokens accepted by the API. Token verification is bypassed if empty
+    #[serde(deserialize_with = "deserialize_tokens")]
+    pub auth_tokens: Vec<TokenConfig>,
+    /// Tokens granted all the scopes, whatever their `scopes`. Admin routes are disabled
+    /// if no token has the ADMIN scope
+    #[serde(deserialize_with = "deserialize_tokens")]
+    pub admin_tokens: Vec<TokenConfig>,
+    /// Networks allowed to use the API. All of them if empty
+    #[serde(deserialize_with = "deserialize_networks")]
+    pub allowed_ips: Vec<IpNet>,
+    /// Networks denied access, even if allowed
+    #[serde(deserialize_with = "deserialize_networks")]
+    pub denied_ips: Vec<IpNet>,
+    /// Load balancers and proxies whose `X-Forwarded-For` header is honoured
+    #[serde(deserialize_with = "deserialize_networks")]
+    pub trusted_proxies: Vec<IpNet>,
+    pub mode: AuthMode,
+    pub jwt: JwtConfig,
+}
+
+/// How requests are authenticated
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum AuthMode {
+    /// Tokens of `auth_tokens` and `admin_tokens`
+    #[default]
+    TOKEN,
+    /// JWTs signed by one of the keys of a JWKS. Token lists are ignored
+    JWT,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JwtConfig {
+    /// Local JWKS file
+    pub jwks_path: Option<PathBuf>,
+    /// JWKS URL, such as the `jwks_uri` of an OpenID Connect provider
+    pub jwks_url: Option<String>,
+    /// Seconds between two reloads of the JWKS, at least 60. Unknown key ids also
+    /// reload it, at most once a minute
+    pub jwks_refresh: u64,
+    /// Expected `iss` claim
+    pub issuer: String,
+    /// Expected `aud` claim, or one of the `aud` values
+    pub audience: String,
+    /// Claim holding the scopes, as a space separated string or a list
+    pub scopes_claim: String,
+    /// Scopes granted by the values of the scopes claim. Values named after
+    /// a scope, such as `READ_AIRPORTS`, grant it as well
+    pub scope_mapping: HashMap<String, Vec<TokenScope>>,
+    /// Tolerated clock skew for `exp` and `nbf`, in seconds
+    pub leeway: u64,
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        JwtConfig {
+            jwks_path: None,
+            jwks_url: None,
+            jwks_refresh: 3600,
+            issuer: String::new(),
+            audience: String::new(),
+            scopes_claim: "scope".to_string(),
+            scope_mapping: HashMap::new(),
+            leeway: 60,
+        }
+    }
+}
+
+/// Routes a token gives access to
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum TokenScope {
+    /// /airport routes
+    READ_AIRPORTS,
+    /// /navaid routes
+    READ_NAVAIDS,
+    /// /admin routes
+    ADMIN,
+}
+
+impl FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<TokenScope, Self::Err> {
+        match input.to_uppercase().as_str() {
+            "READ_AIRPORTS" => Ok(TokenScope::READ_AIRPORTS),
+            "READ_NAVAIDS" => Ok(TokenScope::READ_NAVAIDS),
+            "ADMIN" => Ok(TokenScope::ADMIN),
+            _ => Err(format!(
+                "Unknown scope {}, expected READ_AIRPORTS, READ_NAVAIDS or ADMIN",
+                input
+            )),
+        }
+    }
+}
+
+/// API token. Given either as the token alone, or with its metadata
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenConfig {
+    /// Plaintext token, or SHA-256 hash of the token prefixed by `sha256:`
+    pub token: String,
+    /// Identifies the token in the logs and usage counters
+    pub name: Option<String>,
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<TokenScope>,
+    /// Last day of validity, UTC. The token never expires if not set
+    pub expires: Option<NaiveDate>,
+    /// Unlimited if not set
+    pub requests_per_minute: Option<u32>,
+    /// Networks the token can be used from. When the token has its own address
+    /// lists, they replace the global ones. All of them if empty
+    #[serde(default, deserialize_with = "deserialize_networks")]
+    pub allowed_ips: Vec<IpNet>,
+    #[serde(default, deserialize_with = "deserialize_networks")]
+    pub denied_ips: Vec<IpNet>,
+}
+
+impl From<String> for TokenConfig {
+    fn from(token: String) -> Self {
+        TokenConfig {
+            token,
+            name: None,
+            scopes: default_scopes(),
+            expires: None,
+            requests_per_minute: None,
+            allowed_ips: Vec::new(),
+            denied_ips: Vec::new(),
+        }
+    }
+}
+
+/// Token lists accept plain strings as well as detailed tokens
+fn deserialize_tokens<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<TokenConfig>, D::Error> {
+    struct TokenEntry(TokenConfig);
+
+    impl<'de> Deserialize<'de> for TokenEntry {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_any(TokenVisitor).map(TokenEntry)
+        }
+    }
+
+    struct TokenVisitor;
+
+    impl<'de> Visitor<'de> for TokenVisitor {
+        type Value = TokenConfig;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a token, or a map with the token and its metadata")
+        }
+
+        fn visit_str<E: de::Error>(self, token: &str) -> Result<TokenConfig, E> {
+            Ok(TokenConfig::from(token.to_string()))
+        }
+
+        // Unquoted numeric tokens
+        fn visit_u64<E: de::Error>(self, token: u64) -> Result<TokenConfig, E> {
+            Ok(TokenConfig::from(token.to_string()))
+        }
+
+        fn visit_i64<E: de::Error>(self, token: i64) -> Result<TokenConfig, E> {
+            Ok(TokenConfig::from(token.to_string()))
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<TokenConfig, A::Error> {
+            TokenConfig::deserialize(de::value::MapAccessDeserializer::new(map))
+        }
+    }
+
+    let entries = Vec::<TokenEntry>::deserialize(deserializer)?;
+    Ok(entries.into_iter().map(|TokenEntry(token)| token).collect())
+}
+
+/// Network in CIDR notation, or single address
+pub fn parse_network(value: &str) -> Result<IpNet, String> {
+    value
+        .parse::<IpNet>()
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("{} is neither an IP address nor a CIDR network", value))
+}
+
+fn deserialize_networks<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<IpNet>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|network| parse_network(network).map_err(de::Error::custom))
+        .collect()
+}
+
+/// Read access to all the data
+fn default_scopes() -> Vec<TokenScope> {
+    vec![TokenScope::READ_AIRPORTS, TokenScope::READ_NAVAIDS]
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub backend: BackendType,
+    pub path: Option<String>,
+    pub data_source: DataSourceConfig,
+    /// Snapshot imported at startup when the database is empty, instead of
+    /// waiting for the data source
+    pub snapshot: Option<PathBuf>,
+    pub refresh: RefreshConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DataSourceConfig {
+    /// Local directory, .tar.zst or .zip archive or .navdata.zst snapshot holding the CSV files.
+    /// If not set, files are downloaded from the remote repository
+    pub path: Option<PathBuf>,
+    /// Remote repository API root, GitHub API format
+    pub api_url: String,
+    /// Remote repository raw files root
+    pub raw_url: String,
+    pub branch: String,
+    /// Pins the data to a commit of the remote repository. Overrides the branch
+    pub commit: Option<String>,
+}
+
+impl Default for DataSourceConfig {
+    fn default() -> Self {
+        DataSourceConfig {
+            path: None,
+            api_url: DEFAULT_API_URL.to_string(),
+            raw_url: DEFAULT_RAW_URL.to_string(),
+            branch: DEFAULT_BRANCH.to_string(),
+            commit: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RefreshConfig {
+    pub mode: RefreshMode,
+    /// Seconds between two refreshes, in INTERVAL mode
+    pub interval: u64,
+    /// Cron expression, in UTC, in CRON mode : "sec min hour day_of_month month day_of_week"
+    pub cron: Option<String>,
+    /// Whether the data is loaded before the server starts
+    pub startup: StartupMode,
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        RefreshConfig {
+            mode: RefreshMode::default(),
+            interval: 86400,
+            cron: None,
+            startup: StartupMode::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+    use uuid::Uuid;
+
+    /// Tests setting environment variables run one at a time
+    static ENVIRONMENT: Mutex<()> = Mutex::new(());
+
+    /// `NAVDATA_*` variables set until dropped
+    struct Environment {
+        names: Vec<String>,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl Drop for Environment {
+        fn drop(&mut self) {
+            for name in &self.names {
+                env::remove_var(name);
+            }
+        }
+    }
+
+    fn environment(variables: &[(&str, &str)]) -> Environment {
+        let lock = ENVIRONMENT.lock().unwrap_or_else(PoisonError::into_inner);
+        let names = variables
+            .iter()
+            .map(|(name, value)| {
+                let name = format!("{}{}", ENV_PREFIX, name);
+                env::set_var(&name, value);
+                name
+            })
+            .collect();
+        Environment { names, _lock: lock }
+    }
+
+    /// Loads the YAML as a config file, with the environment
+    fn load(yaml: &str) -> Result<Config, ConfigError> {
+        let path = env::temp_dir().join(format!("navdata-config-{}.yaml", Uuid::new_v4()));
+        std::fs::write(&path, yaml).unwrap();
+        let config = Config::load(Some(&path));
+        std::fs::remove_file(path).unwrap();
+        config
+    }
+
+    fn rejection(yaml: &str) -> String {
+        let config: Config = serde_yaml::from_str(yaml).unwrap();
+        match config.validate() {
+            Ok(()) => panic!("accepted {}", yaml),
+            Err(err) => err.to_string(),
+        }
+    }
+
+    #[test]
+    fn config_files_override_the_defaults() {
+        let _environment = environment(&[]);
+        let defaults = Config::load(None).unwrap();
+        assert_eq!(defaults.http.host, "127.0.0.1");
+        assert_eq!(defaults.http.port, 8080);
+
+        let config = load("http:\n  port: 9000\n").unwrap();
+        assert_eq!(config.http.port, 9000);
+        assert_eq!(config.http.host, "127.0.0.1");
+        assert_eq!(config.database.refresh.interval, 86400);
+
+        let missing = env::temp_dir().join(format!("navdata-{}.yaml", Uuid::new_v4()));
+        assert_eq!(Config::load(Some(&missing)).unwrap().http.port, 8080);
+    }
+
+    #[test]
+    fn invalid_config_files_are_rejected() {
+        let _environment = environment(&[]);
+        let err = load("http:\n  prot: 9000\n").unwrap_err();
+        assert!(matches!(err, ConfigError::File { .. }), "{}", err);
+        let err = load("http:\n  port: [\n").unwrap_err();
+        assert!(matches!(err, ConfigError::File { .. }), "{}", err);
+    }
+
+    #[test]
+    fn environment_overrides_the_config_file() {
+        let _environment = environment(&[
+            ("PORT", "9100"),
+            ("TOKEN_LIST", "aaaa, bbbb,,"),
+            ("ALLOWED_IPS", "10.0.0.0/8,192.168.1.1"),
+            ("REFRESH_MODE", "AIRAC"),
+        ]);
+        let config =
+            load("http:\n  host: 0.0.0.0\n  port: 9000\nsecurity:\n  auth_tokens: [cccc]\n")
+                .unwrap();
+        assert_eq!(config.http.port, 9100);
+        assert_eq!(config.http.host, "0.0.0.0");
+        let tokens: Vec<&str> = config
+            .security
+            .auth_tokens
+            .iter()
+            .map(|token| token.token.as_str())
+            .collect();
+        assert_eq!(tokens, ["aaaa", "bbbb"]);
+        assert_eq!(
+            config.security.allowed_ips,
+            [
+                parse_network("10.0.0.0/8").unwrap(),
+                parse_network("192.168.1.1").unwrap()
+            ]
+        );
+        assert!(matches!(config.database.refresh.mode, RefreshMode::AIRAC));
+    }
+
+    #[test]
+    fn empty_environment_variables_are_ignored() {
+        let _environment = environment(&[("PORT", ""), ("HOST", "  "), ("TOKEN_LIST", "")]);
+        let config = load("http:\n  port: 9000\nsecurity:\n  auth_tokens: [cccc]\n").unwrap();
+        assert_eq!(config.http.port, 9000);
+        assert_eq!(config.http.host, "127.0.0.1");
+        assert_eq!(config.security.auth_tokens.len(), 1);
+    }
+
+    #[test]
+    fn the_mongodb_shortcut_is_overridden_by_the_generic_variables() {
+        let _environment = environment(&[("MONGODB_URL", "mongodb://db:27017")]);
+        let config = load("").unwrap();
+        assert!(matches!(config.database.backend, BackendType::MONGODB));
+        assert_eq!(config.database.path.as_deref(), Some("mongodb://db:27017"));
+        drop(_environment);
+
+        let _environment = environment(&[
+            ("MONGODB_URL", "mongodb://db:27017"),
+            ("BACKEND", "SQLITE"),
+            ("DATABASE_PATH", "/data/navdata.db"),
+        ]);
+        let config = load("").unwrap();
+        assert!(matches!(config.database.backend, BackendType::SQLITE));
+        assert_eq!(config.database.path.as_deref(), Some("/data/navdata.db"));
+    }
+
+    #[test]
+    fn invalid_environment_values_name_the_variable() {
+        for (name, value) in [
+            ("PORT", "http"),
+            ("BACKEND", "ORACLE"),
+            ("DENIED_IPS", "10.0.0.0/33"),
+        ] {
+            let _environment = environment(&[(name, value)]);
+            match load("") {
+                Err(ConfigError::Environment { name: variable, .. }) => {
+                    assert_eq!(variable, format!("{}{}", ENV_PREFIX, name))
+                }
+                other => panic!("{} accepted : {:?}", name, other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn the_default_config_is_valid() {
+        assert!(Config::default().validate().is_ok());
+        let config: Config = serde_yaml::from_str(
+            "http:\n  cors:\n    allowed_origins: [\"https://app.example.com:8443\"]\n    allow_credentials: true\nsecurity:\n  auth_tokens: [\"sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08\"]\n",
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn inconsistent_configs_are_rejected() {
+        let missing = env::temp_dir().join(format!("navdata-{}", Uuid::new_v4()));
+        let missing = missing.display();
+        for (yaml, error) in [
+            ("http:\n  host: ''\n".to_string(), "http.host must not be empty"),
+            ("http:\n  port: 0\n".to_string(), "http.port"),
+            (
+                "http:\n  cors:\n    allowed_origins: [example.com]\n".to_string(),
+                "example.com must be * or scheme://host[:port]",
+            ),
+            (
+                "http:\n  cors:\n    allowed_origins: [\"https://example.com/api\"]\n".to_string(),
+                "without path",
+            ),
+            (
+                "http:\n  cors:\n    allowed_origins: ['*']\n    allow_credentials: true\n"
+                    .to_string(),
+                "allow_credentials is not allowed with the * origin",
+            ),
+            (
+                "http:\n  cors:\n    allowed_methods: [\"GET POST\"]\n".to_string(),
+                "GET POST is not a valid method or header name",
+            ),
+            (
+                "security:\n  auth_tokens: ['']\n".to_string(),
+                "tokens must not be empty",
+            ),
+            (
+                "security:\n  admin_tokens:\n    - token: aaaa\n      requests_per_minute: 0\n"
+                    .to_string(),
+                "requests_per_minute must be at least 1",
+            ),
+            (
+                "security:\n  auth_tokens: ['sha256:abcd']\n".to_string(),
+                "must be 64 hexadecimal characters",
+            ),
+            (
+                "security:\n  mode: JWT\n  jwt:\n    issuer: sso\n    audience: navdata\n"
+                    .to_string(),
+                "exactly one of security.jwt.jwks_path and security.jwt.jwks_url",
+            ),
+            (
+                format!("security:\n  mode: JWT\n  jwt:\n    jwks_path: {}\n    jwks_url: https://sso/jwks\n    issuer: sso\n    audience: navdata\n", missing),
+                "exactly one of security.jwt.jwks_path and security.jwt.jwks_url",
+            ),
+            (
+                format!("security:\n  mode: JWT\n  jwt:\n    jwks_path: {}\n    issuer: sso\n    audience: navdata\n", missing),
+                "does not exist",
+            ),
+            (
+                "security:\n  mode: JWT\n  jwt:\n    jwks_url: ftp://sso/jwks\n    issuer: sso\n    audience: navdata\n"
+                    .to_string(),
+                "jwks_url must be an http(s) URL",
+            ),
+            (
+                "security:\n  mode: JWT\n  jwt:\n    jwks_url: https://sso/jwks\n    issuer: sso\n"
+                    .to_string(),
+                "issuer and security.jwt.audience must be set",
+            ),
+            (
+                "security:\n  mode: JWT\n  jwt:\n    jwks_url: https://sso/jwks\n    jwks_refresh: 0\n    issuer: sso\n    audience: navdata\n"
+                    .to_string(),
+                "security.jwt.jwks_refresh must be at least 60 seconds",
+            ),
+            (
+                "database:\n  backend: MONGODB\n  path: /data/navdata.db\n".to_string(),
+                "mongodb:// URL",
+            ),
+            (
+                "database:\n  backend: POSTGRES\n".to_string(),
+                "postgresql:// URL",
+            ),
+            (
+                format!("database:\n  data_source:\n    path: {}\n", missing),
+                "database.data_source.path",
+            ),
+            (
+                format!("database:\n  snapshot: {}\n", missing),
+                "database.snapshot",
+            ),
+            (
+                "database:\n  refresh:\n    mode: CRON\n".to_string(),
+                "database.refresh : A cron expression is required",
+            ),
+        ] {
+            let rejection = rejection(&yaml);
+            assert!(rejection.contains(error), "{} : {}", yaml, rejection);
+        }
+    }
+}
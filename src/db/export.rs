@@ -0,0 +1,215 @@
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::error::Error;
+use std::io::Write;
+
+use super::{Airport, LocationPoint, NavDataStore, Navaid, SearchFilter, MAX_PAGE_SIZE};
+
+/// Formats the database content can be exported to
+#[derive(Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    GeoJson,
+}
+
+/// Items that can be exported. CSV exports are flat, without the airport details
+pub trait Exportable: Serialize {
+    fn csv_header() -> &'static [&'static str];
+
+    fn csv_record(&self) -> Vec<String>;
+
+    fn location(&self) -> &LocationPoint;
+}
+
+impl Exportable for Airport {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "id",
+            "icao_code",
+            "type",
+            "name",
+            "latitude",
+            "longitude",
+            "elevation_ft",
+            "continent",
+            "iso_country",
+            "iso_region",
+            "municipality",
+            "scheduled_service",
+            "gps_code",
+            "iata_code",
+            "local_code",
+            "home_link",
+            "wikipedia_link",
+            "keywords",
+        ]
+    }
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.icao_code.clone(),
+            self.r#type.to_string(),
+            self.name.clone(),
+            self.location.latitude().to_string(),
+            self.location.longitude().to_string(),
+            self.elevation_ft.to_string(),
+            self.continent.clone(),
+            self.iso_country.clone(),
+            self.iso_region.clone(),
+            self.municipality.clone(),
+            self.scheduled_service.clone(),
+            self.gps_code.clone(),
+            self.iata_code.clone(),
+            self.local_code.clone(),
+            self.home_link.clone(),
+            self.wikipedia_link.clone(),
+            self.keywords.clone(),
+        ]
+    }
+
+    fn location(&self) -> &LocationPoint {
+        &self.location
+    }
+}
+
+impl Exportable for Navaid {
+    fn csv_header() -> &'static [&'static str] {
+        &[
+            "id",
+            "filename",
+            "icao_code",
+            "name",
+            "type",
+            "frequency_khz",
+            "latitude",
+            "longitude",
+            "elevation_ft",
+            "iso_country",
+            "dme_frequency_khz",
+            "dme_channel",
+            "dme_latitude",
+            "dme_longitude",
+            "dme_elevation_ft",
+            "slaved_variation_deg",
+            "magnetic_variation_deg",
+            "usage_type",
+            "power",
+            "associated_airport",
+        ]
+    }
+
+    fn csv_record(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.filename.clone(),
+            self.icao_code.clone(),
+            self.name.clone(),
+            self.r#type.to_string(),
+            self.frequency_khz.to_string(),
+            self.location.latitude().to_string(),
+            self.location.longitude().to_string(),
+            self.elevation_ft.to_string(),
+            self.iso_country.clone(),
+            self.dme_frequency_khz.to_string(),
+            self.dme_channel.clone(),
+            self.dme_location.latitude().to_string(),
+            self.dme_location.longitude().to_string(),
+            self.dme_elevation_ft.to_string(),
+            self.slaved_variation_deg.to_string(),
+            self.magnetic_variation_deg.to_string(),
+            self.usage_type.clone(),
+            self.power.clone(),
+            self.associated_airport.clone(),
+        ]
+    }
+
+    fn location(&self) -> &LocationPoint {
+        &self.location
+    }
+}
+
+/// Every airport of the database, read page by page
+pub async fn all_airports(
+    store: &dyn NavDataStore,
+    skip_details: bool,
+) -> Result<Vec<Airport>, Box<dyn Error>> {
+    let mut airports = vec![];
+    for page in 0.. {
+        let filter = SearchFilter {
+            page: Some(page),
+            skip_details,
+            ..Default::default()
+        };
+        let found = store.search_airport(filter).await?;
+        let last_page = (found.len() as u64) < MAX_PAGE_SIZE;
+        airports.extend(found);
+        if last_page {
+            break;
+        }
+    }
+    Ok(airports)
+}
+
+/// Every navaid of the database, read page by page
+pub async fn all_navaids(store: &dyn NavDataStore) -> Result<Vec<Navaid>, Box<dyn Error>> {
+    let mut navaids = vec![];
+    for page in 0.. {
+        let filter = SearchFilter {
+            page: Some(page),
+            ..Default::default()
+        };
+        let found = store.search_navaid(filter).await?;
+        let last_page = (found.len() as u64) < MAX_PAGE_SIZE;
+        navaids.extend(found);
+        if last_page {
+            break;
+        }
+    }
+    Ok(navaids)
+}
+
+pub fn write_items<T: Exportable>(
+    items: &[T],
+    format: ExportFormat,
+    output: impl Write,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(output);
+            writer.write_record(T::csv_header())?;
+            for item in items {
+                writer.write_record(item.csv_record())?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Json => serde_json::to_writer(output, items)?,
+        ExportFormat::GeoJson => {
+            let features = items
+                .iter()
+                .map(geojson_feature)
+                .collect::<Result<Vec<Value>, _>>()?;
+            serde_json::to_writer(
+                output,
+                &json!({"type": "FeatureCollection", "features": features}),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// GeoJSON feature of an item, with its location as geometry and all its other
+/// fields as properties
+fn geojson_feature<T: Exportable>(item: &T) -> Result<Value, serde_json::Error> {
+    let mut properties = match serde_json::to_value(item)? {
+        Value::Object(fields) => fields,
+        _ => Map::new(),
+    };
+    properties.remove("location");
+    Ok(json!({
+        "type": "Feature",
+        "geometry": item.location(),
+        "properties": properties,
+    }))
+}
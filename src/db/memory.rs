@@ -0,0 +1,752 @@
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use csv::StringRecord;
+use log::{error, info};
+use rstar::primitives::GeomWithData;
+use rstar::{RTree, AABB};
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::f64::consts::PI;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use super::source::DataSource;
+use super::update::{now, with_retry, LoadedFile, UpdateError, UpdateStatus};
+use super::{
+    Airport, AirportType, BoundingBox, Frequency, FrequencyType, LocationPoint, LocationType,
+    NavDataStore, Navaid, NavaidType, Runway, SearchFilter, AIRPORT_CSV, AIRPORT_FREQUENCY_CSV,
+    AIRPORT_RUNWAY_CSV, CSV_FILES, EARTH_RADIUS_NM, NAVAID_CSV,
+};
+use crate::messages::{CSV_FORMAT_ERROR, ERROR_UPDATE_STATUS_ACCESS};
+
+/// Point on the unit sphere. Chord lengths between such points grow with the
+/// great-circle distance, so the nearest points in the R-tree are the nearest on earth
+type SpherePoint = GeomWithData<[f64; 3], usize>;
+/// Longitude and latitude, for bounding box searchs
+type MapPoint = GeomWithData<[f64; 2], usize>;
+
+const AIRPORT_COLUMNS: usize = 18;
+const AIRPORT_FREQUENCY_COLUMNS: usize = 6;
+const AIRPORT_RUNWAY_COLUMNS: usize = 20;
+const NAVAID_COLUMNS: usize = 20;
+
+/// Backend holding the data in memory. The indexes are rebuilt on each refresh
+/// and swapped atomically, readers never wait
+#[derive(Default)]
+pub struct MemoryBackend {
+    index: ArcSwap<MemoryIndex>,
+    status: Mutex<UpdateStatus>,
+}
+
+impl MemoryBackend {
+    /// Parses a file if it changed. Returns the current items if it did not
+    #[allow(clippy::too_many_arguments)]
+    async fn load_file<T>(
+        &self,
+        source: &DataSource,
+        file: &str,
+        sha: Option<&String>,
+        current: &Arc<Vec<T>>,
+        files: &mut HashMap<String, LoadedFile>,
+        columns: usize,
+        read: fn(&StringRecord) -> T,
+    ) -> Result<Arc<Vec<T>>, UpdateError> {
+        let sha = sha.ok_or_else(|| UpdateError::MissingFile(file.to_string()))?;
+        if files.get(file).is_some_and(|loaded| &loaded.sha == sha) {
+            info!("{} is up to date", file);
+            return Ok(current.clone());
+        }
+
+        let data = with_retry(&format!("Downloading {}", file), || source.fetch(file))
+            .await
+            .map_err(|reason| UpdateError::Fetch {
+                file: file.to_string(),
+                reason,
+            })?;
+        let items = read_csv(&data, columns, read).map_err(|err| UpdateError::Load {
+            file: file.to_string(),
+            reason: err.to_string(),
+        })?;
+        info!("{} records of {} loaded", items.len(), file);
+        files.insert(
+            file.to_string(),
+            LoadedFile {
+                file: file.to_string(),
+                sha: sha.clone(),
+                date: now(),
+                rows: items.len() as i64,
+            },
+        );
+        Ok(Arc::new(items))
+    }
+
+    /// Records the outcome of a file load. The current items are kept when it failed
+    fn keep_loaded<T>(
+        &self,
+        file: &str,
+        loaded: Result<Arc<Vec<T>>, UpdateError>,
+        current: &Arc<Vec<T>>,
+        files: &HashMap<String, LoadedFile>,
+        result: &mut Result<(), UpdateError>,
+    ) -> Arc<Vec<T>> {
+        let file_result = match &loaded {
+            Ok(_) => Ok(files.get(file).map(|loaded| loaded.sha.clone())),
+            Err(err) => Err(err.clone()),
+        };
+        self.status
+            .lock()
+            .expect(ERROR_UPDATE_STATUS_ACCESS)
+            .record_file(file, &file_result);
+        match loaded {
+            Ok(items) => items,
+            Err(err) => {
+                error!("{}", err);
+                *result = Err(err);
+                current.clone()
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl NavDataStore for MemoryBackend {
+    async fn get_airport_by_icao_code(
+        &self,
+        icao: String,
+    ) -> Result<Option<Airport>, Box<dyn Error>> {
+        let index = self.index.load();
+        let airport = index
+            .airports
+            .with_code(&icao)
+            .find(|airport| airport.icao_code.eq_ignore_ascii_case(&icao))
+            .map(|airport| index.with_details(airport.clone()));
+        Ok(airport)
+    }
+
+    async fn get_navaids_by_icao_code(&self, icao: String) -> Result<Vec<Navaid>, Box<dyn Error>> {
+        Ok(self
+            .index
+            .load()
+            .navaids
+            .with_code(&icao)
+            .filter(|navaid| navaid.icao_code.eq_ignore_ascii_case(&icao))
+            .cloned()
+            .collect())
+    }
+
+    async fn search_airport(&self, filter: SearchFilter) -> Result<Vec<Airport>, Box<dyn Error>> {
+        let index = self.index.load();
+        Ok(index
+            .airports
+            .search(&filter)
+            .into_iter()
+            .map(|(airport, distance)| Airport {
+                distance_nm: distance,
+                ..match filter.skip_details {
+                    true => airport,
+                    false => index.with_details(airport),
+                }
+            })
+            .collect())
+    }
+
+    async fn search_navaid(&self, filter: SearchFilter) -> Result<Vec<Navaid>, Box<dyn Error>> {
+        Ok(self
+            .index
+            .load()
+            .navaids
+            .search(&filter)
+            .into_iter()
+            .map(|(navaid, distance)| Navaid {
+                distance_nm: distance,
+                ..navaid
+            })
+            .collect())
+    }
+
+    /// Parses the files whose sha changed and swaps in new indexes. A failing file
+    /// keeps its previous data
+    async fn load(&self, source: &DataSource) -> Result<(), UpdateError> {
+        let shas = with_retry("Listing data files", || source.get_list_of_sha())
+            .await
+            .map_err(UpdateError::Source)?;
+
+        let current = self.index.load_full();
+        let mut files = current.files.clone();
+        let mut result = Ok(());
+
+        let loaded = self
+            .load_file(
+                source,
+                AIRPORT_CSV,
+                shas.get(AIRPORT_CSV),
+                &current.airports.items,
+                &mut files,
+                AIRPORT_COLUMNS,
+                read_airport,
+            )
+            .await;
+        let airports = self.keep_loaded(
+            AIRPORT_CSV,
+            loaded,
+            &current.airports.items,
+            &files,
+            &mut result,
+        );
+        let loaded = self
+            .load_file(
+                source,
+                AIRPORT_FREQUENCY_CSV,
+                shas.get(AIRPORT_FREQUENCY_CSV),
+                &current.frequencies,
+                &mut files,
+                AIRPORT_FREQUENCY_COLUMNS,
+                read_frequency,
+            )
+            .await;
+        let frequencies = self.keep_loaded(
+            AIRPORT_FREQUENCY_CSV,
+            loaded,
+            &current.frequencies,
+            &files,
+            &mut result,
+        );
+        let loaded = self
+            .load_file(
+                source,
+                AIRPORT_RUNWAY_CSV,
+                shas.get(AIRPORT_RUNWAY_CSV),
+                &current.runways,
+                &mut files,
+                AIRPORT_RUNWAY_COLUMNS,
+                read_runway,
+            )
+            .await;
+        let runways = self.keep_loaded(
+            AIRPORT_RUNWAY_CSV,
+            loaded,
+            &current.runways,
+            &files,
+            &mut result,
+        );
+        let loaded = self
+            .load_file(
+                source,
+                NAVAID_CSV,
+                shas.get(NAVAID_CSV),
+                &current.navaids.items,
+                &mut files,
+                NAVAID_COLUMNS,
+                read_navaid,
+            )
+            .await;
+        let navaids = self.keep_loaded(
+            NAVAID_CSV,
+            loaded,
+            &current.navaids.items,
+            &files,
+            &mut result,
+        );
+
+        self.index.store(Arc::new(MemoryIndex::new(
+            airports,
+            frequencies,
+            runways,
+            navaids,
+            files,
+        )));
+        info!("Memory indexes rebuilt");
+        result
+    }
+
+    async fn loaded_files(&self) -> Result<Vec<LoadedFile>, Box<dyn Error>> {
+        let index = self.index.load();
+        Ok(CSV_FILES
+            .iter()
+            .filter_map(|file| index.files.get(*file).cloned())
+            .collect())
+    }
+
+    fn update_status(&self) -> &Mutex<UpdateStatus> {
+        &self.status
+    }
+}
+
+/// Immutable set of the loaded data and its indexes
+struct MemoryIndex {
+    airports: Collection<Airport>,
+    navaids: Collection<Navaid>,
+    frequencies: Arc<Vec<Frequency>>,
+    runways: Arc<Vec<Runway>>,
+    /// Frequency positions by airport ICAO code
+    frequencies_by_airport: HashMap<String, Vec<usize>>,
+    /// Runway positions by airport ICAO code
+    runways_by_airport: HashMap<String, Vec<usize>>,
+    /// Loaded files, by file name
+    files: HashMap<String, LoadedFile>,
+}
+
+impl Default for MemoryIndex {
+    fn default() -> Self {
+        MemoryIndex::new(
+            Arc::default(),
+            Arc::default(),
+            Arc::default(),
+            Arc::default(),
+            HashMap::new(),
+        )
+    }
+}
+
+impl MemoryIndex {
+    fn new(
+        airports: Arc<Vec<Airport>>,
+        frequencies: Arc<Vec<Frequency>>,
+        runways: Arc<Vec<Runway>>,
+        navaids: Arc<Vec<Navaid>>,
+        files: HashMap<String, LoadedFile>,
+    ) -> MemoryIndex {
+        let mut frequencies_by_airport: HashMap<String, Vec<usize>> = HashMap::new();
+        for (position, frequency) in frequencies.iter().enumerate() {
+            frequencies_by_airport
+                .entry(frequency.airport_icao_code.clone())
+                .or_default()
+                .push(position);
+        }
+        let mut runways_by_airport: HashMap<String, Vec<usize>> = HashMap::new();
+        for (position, runway) in runways.iter().enumerate() {
+            runways_by_airport
+                .entry(runway.airport_icao_code.clone())
+                .or_default()
+                .push(position);
+        }
+        MemoryIndex {
+            airports: Collection::new(airports),
+            navaids: Collection::new(navaids),
+            frequencies,
+            runways,
+            frequencies_by_airport,
+            runways_by_airport,
+            files,
+        }
+    }
+
+    /// Adds the runways, frequencies and navaids of the airport
+    fn with_details(&self, airport: Airport) -> Airport {
+        let runways = self
+            .runways_by_airport
+            .get(&airport.icao_code)
+            .into_iter()
+            .flatten()
+            .map(|&position| self.runways[position].clone())
+            .collect();
+        let frequencies = self
+            .frequencies_by_airport
+            .get(&airport.icao_code)
+            .into_iter()
+            .flatten()
+            .map(|&position| self.frequencies[position].clone())
+            .collect();
+        let navaids = self
+            .navaids
+            .with_code(&airport.icao_code)
+            .filter(|navaid| navaid.associated_airport == airport.icao_code)
+            .cloned()
+            .collect();
+        Airport {
+            runways,
+            frequencies,
+            navaids,
+            ..airport
+        }
+    }
+}
+
+/// Items searched by the memory backend
+trait Searchable {
+    fn location(&self) -> &LocationPoint;
+    fn country(&self) -> &str;
+    fn has_type(&self, r#type: &str) -> bool;
+    /// Codes the item is looked up with
+    fn codes(&self) -> [&str; 2];
+    /// Texts searched, along with the codes. A text search matches when one of
+    /// them contains the searched text, ignoring case, as on the other backends
+    fn texts(&self) -> Vec<&str>;
+}
+
+impl Searchable for Airport {
+    fn location(&self) -> &LocationPoint {
+        &self.location
+    }
+
+    fn country(&self) -> &str {
+        &self.iso_country
+    }
+
+    fn has_type(&self, r#type: &str) -> bool {
+        self.r#type.to_string() == r#type.to_lowercase()
+    }
+
+    fn codes(&self) -> [&str; 2] {
+        [&self.icao_code, &self.iata_code]
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        vec![&self.name, &self.municipality]
+    }
+}
+
+impl Searchable for Navaid {
+    fn location(&self) -> &LocationPoint {
+        &self.location
+    }
+
+    fn country(&self) -> &str {
+        &self.iso_country
+    }
+
+    fn has_type(&self, r#type: &str) -> bool {
+        self.r#type.to_string() == r#type.to_uppercase()
+    }
+
+    fn codes(&self) -> [&str; 2] {
+        [&self.icao_code, &self.associated_airport]
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        vec![&self.name]
+    }
+}
+
+/// Items with their code, trigram and spatial indexes. Indexes hold item positions
+struct Collection<T> {
+    items: Arc<Vec<T>>,
+    /// Positions by uppercase code
+    codes: HashMap<String, Vec<usize>>,
+    /// Lowercase searched texts and codes of each item, separated by newlines
+    texts: Vec<String>,
+    /// Positions by lowercase trigram of the searched texts and codes
+    trigrams: HashMap<[char; 3], Vec<usize>>,
+    points: RTree<SpherePoint>,
+    map: RTree<MapPoint>,
+}
+
+impl<T: Searchable + Clone> Collection<T> {
+    fn new(items: Arc<Vec<T>>) -> Collection<T> {
+        let mut codes: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut texts = Vec::with_capacity(items.len());
+        let mut trigrams: HashMap<[char; 3], Vec<usize>> = HashMap::new();
+        let mut points = vec![];
+        let mut map = vec![];
+        for (position, item) in items.iter().enumerate() {
+            for code in item.codes().iter().filter(|code| !code.is_empty()) {
+                push_position(codes.entry(code.to_uppercase()).or_default(), position);
+            }
+            let searched = item
+                .texts()
+                .into_iter()
+                .chain(item.codes())
+                .map(str::to_lowercase)
+                .collect::<Vec<String>>();
+            for text in &searched {
+                for trigram in split_trigrams(text) {
+                    push_position(trigrams.entry(trigram).or_default(), position);
+                }
+            }
+            texts.push(searched.join("\n"));
+            let location = item.location();
+            points.push(SpherePoint::new(
+                sphere_coordinates(location.latitude(), location.longitude()),
+                position,
+            ));
+            map.push(MapPoint::new(
+                [location.longitude(), location.latitude()],
+                position,
+            ));
+        }
+        Collection {
+            items,
+            codes,
+            texts,
+            trigrams,
+            points: RTree::bulk_load(points),
+            map: RTree::bulk_load(map),
+        }
+    }
+
+    fn with_code(&self, code: &str) -> impl Iterator<Item = &T> {
+        self.codes
+            .get(&code.to_uppercase())
+            .into_iter()
+            .flatten()
+            .map(|&position| &self.items[position])
+    }
+
+    /// Positions of the items matching a text search. Items holding every trigram
+    /// of the search are then checked for the whole text
+    fn text_matches(&self, search: &str) -> BTreeSet<usize> {
+        let search = search.to_lowercase();
+        if search.contains('\n') {
+            return BTreeSet::new();
+        }
+        let mut candidates: Option<BTreeSet<usize>> = None;
+        for trigram in split_trigrams(&search) {
+            let positions = self
+                .trigrams
+                .get(&trigram)
+                .into_iter()
+                .flatten()
+                .copied()
+                .collect::<BTreeSet<usize>>();
+            candidates = Some(match candidates {
+                Some(previous) => previous.intersection(&positions).copied().collect(),
+                None => positions,
+            });
+        }
+        let contains = |position: &usize| self.texts[*position].contains(&search);
+        match candidates {
+            Some(candidates) => candidates.into_iter().filter(contains).collect(),
+            // Searches shorter than a trigram go through all the items
+            None => (0..self.items.len()).filter(contains).collect(),
+        }
+    }
+
+    /// Positions of the items in the bounding box
+    fn in_bbox(&self, bbox: &BoundingBox) -> BTreeSet<usize> {
+        let envelopes = match bbox.crosses_antimeridian() {
+            true => vec![
+                AABB::from_corners([bbox.min_lon, bbox.min_lat], [180.0, bbox.max_lat]),
+                AABB::from_corners([-180.0, bbox.min_lat], [bbox.max_lon, bbox.max_lat]),
+            ],
+            false => vec![AABB::from_corners(
+                [bbox.min_lon, bbox.min_lat],
+                [bbox.max_lon, bbox.max_lat],
+            )],
+        };
+        envelopes
+            .iter()
+            .flat_map(|envelope| self.map.locate_in_envelope(envelope))
+            .map(|point| point.data)
+            .collect()
+    }
+
+    /// Requested page of the items matching the filter, with their distance to the
+    /// searched point. Proximity searchs are ordered by distance, others by position
+    fn search(&self, filter: &SearchFilter) -> Vec<(T, Option<f64>)> {
+        let text = filter
+            .search
+            .as_ref()
+            .map(|search| self.text_matches(search));
+        let bbox = filter.bbox.as_ref().map(|bbox| self.in_bbox(bbox));
+        let country = filter
+            .country
+            .as_ref()
+            .map(|country| country.to_uppercase());
+        let matches = |position: &usize| {
+            let item = &self.items[*position];
+            text.as_ref().is_none_or(|text| text.contains(position))
+                && bbox.as_ref().is_none_or(|bbox| bbox.contains(position))
+                && country
+                    .as_ref()
+                    .is_none_or(|country| item.country() == country)
+                && filter
+                    .r#type
+                    .as_ref()
+                    .is_none_or(|r#type| item.has_type(r#type))
+        };
+
+        match filter.center() {
+            Some((latitude, longitude)) => {
+                let radius_nm = filter.radius_nm();
+                let angle = radius_nm / EARTH_RADIUS_NM;
+                let max_chord = match angle < PI {
+                    true => 2.0 * (angle / 2.0).sin(),
+                    false => 2.0,
+                };
+                self.points
+                    .nearest_neighbor_iter_with_distance_2(&sphere_coordinates(latitude, longitude))
+                    .take_while(|(_, chord_2)| *chord_2 <= max_chord * max_chord + 1e-12)
+                    .map(|(point, _)| point.data)
+                    .filter(matches)
+                    .map(|position| {
+                        let item = &self.items[position];
+                        let distance = item.location().distance_nm(latitude, longitude);
+                        (item, distance)
+                    })
+                    .filter(|(_, distance)| *distance <= radius_nm)
+                    .skip(filter.offset() as usize)
+                    .take(filter.page_size() as usize)
+                    .map(|(item, distance)| (item.clone(), Some(distance)))
+                    .collect()
+            }
+            None => {
+                let positions: Box<dyn Iterator<Item = usize>> = match (&text, &bbox) {
+                    (Some(text), _) => Box::new(text.iter().copied()),
+                    (None, Some(bbox)) => Box::new(bbox.iter().copied()),
+                    (None, None) => Box::new(0..self.items.len()),
+                };
+                positions
+                    .filter(matches)
+                    .skip(filter.offset() as usize)
+                    .take(filter.page_size() as usize)
+                    .map(|position| (self.items[position].clone(), None))
+                    .collect()
+            }
+        }
+    }
+}
+
+fn push_position(positions: &mut Vec<usize>, position: usize) {
+    if positions.last() != Some(&position) {
+        positions.push(position);
+    }
+}
+
+fn split_trigrams(text: &str) -> Vec<[char; 3]> {
+    let chars = text.chars().collect::<Vec<char>>();
+    chars
+        .windows(3)
+        .map(|window| [window[0], window[1], window[2]])
+        .collect()
+}
+
+fn sphere_coordinates(latitude: f64, longitude: f64) -> [f64; 3] {
+    let (latitude, longitude) = (latitude.to_radians(), longitude.to_radians());
+    [
+        latitude.cos() * longitude.cos(),
+        latitude.cos() * longitude.sin(),
+        latitude.sin(),
+    ]
+}
+
+fn read_csv<T>(
+    data: &str,
+    columns: usize,
+    read: fn(&StringRecord) -> T,
+) -> Result<Vec<T>, Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new().from_reader(data.as_bytes());
+    reader
+        .records()
+        .map(|record| {
+            let record = record?;
+            if record.len() < columns {
+                return Err(CSV_FORMAT_ERROR.into());
+            }
+            Ok(read(&record))
+        })
+        .collect()
+}
+
+fn text(record: &StringRecord, index: usize) -> String {
+    record.get(index).unwrap_or_default().to_string()
+}
+
+/// Integer value of a field. Decimal values are truncated, invalid ones are 0
+fn integer(record: &StringRecord, index: usize) -> i64 {
+    let value = record.get(index).unwrap_or_default().trim();
+    value
+        .parse::<i64>()
+        .or_else(|_| value.parse::<f64>().map(|float| float as i64))
+        .unwrap_or_default()
+}
+
+fn float(record: &StringRecord, index: usize) -> f64 {
+    record
+        .get(index)
+        .unwrap_or_default()
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|float| float.is_finite())
+        .unwrap_or_default()
+}
+
+fn location(record: &StringRecord, latitude: usize, longitude: usize) -> LocationPoint {
+    LocationPoint {
+        r#type: LocationType::Point,
+        coordinates: vec![float(record, longitude), float(record, latitude)],
+    }
+}
+
+fn read_airport(record: &StringRecord) -> Airport {
+    Airport {
+        id: integer(record, 0),
+        icao_code: text(record, 1),
+        r#type: AirportType::from_str(&text(record, 2)).unwrap_or_default(),
+        name: text(record, 3),
+        location: location(record, 4, 5),
+        elevation_ft: integer(record, 6),
+        continent: text(record, 7),
+        iso_country: text(record, 8),
+        iso_region: text(record, 9),
+        municipality: text(record, 10),
+        scheduled_service: text(record, 11),
+        gps_code: text(record, 12),
+        iata_code: text(record, 13),
+        local_code: text(record, 14),
+        home_link: text(record, 15),
+        wikipedia_link: text(record, 16),
+        keywords: text(record, 17),
+        ..Default::default()
+    }
+}
+
+fn read_frequency(record: &StringRecord) -> Frequency {
+    let raw_type = text(record, 3);
+    Frequency {
+        id: integer(record, 0),
+        airport_id: integer(record, 1),
+        airport_icao_code: text(record, 2),
+        r#type: FrequencyType::from_str(&raw_type).unwrap_or_default(),
+        raw_type,
+        description: text(record, 4),
+        frequency_mhz: float(record, 5),
+    }
+}
+
+fn read_runway(record: &StringRecord) -> Runway {
+    Runway {
+        id: integer(record, 0),
+        airport_id: integer(record, 1),
+        airport_icao_code: text(record, 2),
+        length_ft: integer(record, 3),
+        width_ft: integer(record, 4),
+        surface: integer(record, 5),
+        lighted: integer(record, 6),
+        closed: integer(record, 7),
+        le_ident: text(record, 8),
+        le_location: location(record, 9, 10),
+        le_elevation_ft: integer(record, 11),
+        le_heading_deg_t: integer(record, 12),
+        le_displaced_threshold_ft: integer(record, 13),
+        he_ident: text(record, 14),
+        he_location: location(record, 15, 16),
+        he_elevation_ft: integer(record, 17),
+        he_heading_deg_t: integer(record, 18),
+        he_displaced_threshold_ft: integer(record, 19),
+    }
+}
+
+fn read_navaid(record: &StringRecord) -> Navaid {
+    Navaid {
+        id: integer(record, 0),
+        filename: text(record, 1),
+        icao_code: text(record, 2),
+        name: text(record, 3),
+        r#type: NavaidType::from_str(&text(record, 4)).unwrap_or_default(),
+        frequency_khz: integer(record, 5),
+        location: location(record, 6, 7),
+        elevation_ft: integer(record, 8),
+        iso_country: text(record, 9),
+        dme_frequency_khz: integer(record, 10),
+        dme_channel: text(record, 11),
+        dme_location: location(record, 12, 13),
+        dme_elevation_ft: integer(record, 14),
+        slaved_variation_deg: integer(record, 15),
+        magnetic_variation_deg: integer(record, 16),
+        usage_type: text(record, 17),
+        power: text(record, 18),
+        associated_airport: text(record, 19),
+        distance_nm: None,
+    }
+}
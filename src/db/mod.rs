@@ -0,0 +1,880 @@
+use async_trait::async_trait;
+pub mod export;
+pub mod memory;
+pub mod mongodb;
+pub mod postgres;
+pub mod schedule;
+pub mod snapshot;
+pub mod source;
+pub mod sqlite;
+pub mod update;
+use self::memory::MemoryBackend;
+use self::mongodb::MongoDbBackend;
+use self::postgres::PostgresBackend;
+use self::schedule::Schedule;
+use self::source::DataSource;
+use self::sqlite::SqliteBackend;
+use self::update::{end_of_cycle, start_cycle, DatasetMeta, LoadedFile, UpdateError, UpdateStatus};
+use crate::config::Config;
+use crate::messages::{ERROR_DATASET_META_ACCESS, ERROR_UPDATE_STATUS_ACCESS};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::time::sleep;
+
+const AIRPORT_CSV: &str = "airports.csv";
+const AIRPORT_FREQUENCY_CSV: &str = "airport-frequencies.csv";
+const AIRPORT_RUNWAY_CSV: &str = "runways.csv";
+const NAVAID_CSV: &str = "navaids.csv";
+const CSV_FILES: [&str; 4] = [
+    AIRPORT_CSV,
+    AIRPORT_FREQUENCY_CSV,
+    AIRPORT_RUNWAY_CSV,
+    NAVAID_CSV,
+];
+
+/// Mean earth radius, in nautical miles
+const EARTH_RADIUS_NM: f64 = 3440.065;
+/// Length of a nautical mile, in meters
+const METERS_PER_NM: f64 = 1852.0;
+/// Maximum distance of proximity searchs, in nautical miles (5000 km)
+pub const DEFAULT_SEARCH_RADIUS_NM: f64 = 5_000_000.0 / METERS_PER_NM;
+/// Number of results per page, and maximum value of the limit parameter
+pub const MAX_PAGE_SIZE: u64 = 100;
+
+/// Great-circle distance between two points given in degrees, in nautical miles
+pub fn great_circle_distance_nm(
+    latitude1: f64,
+    longitude1: f64,
+    latitude2: f64,
+    longitude2: f64,
+) -> f64 {
+    let (lat1, lat2) = (latitude1.to_radians(), latitude2.to_radians());
+    let delta_lat = lat2 - lat1;
+    let delta_lon = (longitude2 - longitude1).to_radians();
+
+    // Haversine formula
+    let a =
+        (delta_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_NM * a.sqrt().min(1.0).asin()
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub enum AirportType {
+    SmallAirport,
+    MediumAirport,
+    LargeAirport,
+    Heliport,
+    SeaplaneBase,
+    BalloonPort,
+    Closed,
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for AirportType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AirportType::SmallAirport => write!(f, "small_airport"),
+            AirportType::MediumAirport => write!(f, "medium_airport"),
+            AirportType::LargeAirport => write!(f, "large_airport"),
+            AirportType::Heliport => write!(f, "heliport"),
+            AirportType::SeaplaneBase => write!(f, "seaplane_base"),
+            AirportType::BalloonPort => write!(f, "balloonport"),
+            AirportType::Closed => write!(f, "closed"),
+            AirportType::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+impl FromStr for AirportType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<AirportType, Self::Err> {
+        match input {
+            "small_airport" => Ok(AirportType::SmallAirport),
+            "medium_airport" => Ok(AirportType::MediumAirport),
+            "large_airport" => Ok(AirportType::LargeAirport),
+            "heliport" => Ok(AirportType::Heliport),
+            "seaplane_base" => Ok(AirportType::SeaplaneBase),
+            "balloonport" => Ok(AirportType::BalloonPort),
+            "closed" => Ok(AirportType::Closed),
+            _ => {
+                error!("Unknow airport type {}", input);
+                Err(())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub enum FrequencyType {
+    Approach,
+    Departure,
+    Arrival,
+    ApproachDeparture,
+    ArrivalDeparture,
+    SafetyComm,
+    AutoInformation,
+    Clearance,
+    Tower,
+    Ground,
+    Com,
+    Atis,
+    Afis,
+    Info,
+    Ctaf,
+    Arcal,
+    Emergency,
+    Unic,
+    Unicom,
+    Cntr,
+    Artc,
+    AD,
+    AG,
+    Mult,
+    Ops,
+    Atf,
+    Acc,
+    Misc,
+    Pmsv,
+    Traffic,
+    Awos,
+    Asos,
+    Fss,
+    Fcc,
+    Rdo,
+    Rco,
+    Cld,
+    Fia,
+    Ils,
+    Military,
+    Private,
+    Ramp,
+    Apron,
+    Radar,
+    NotControlled,
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for FrequencyType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrequencyType::Approach => write!(f, "APP"),
+            FrequencyType::Departure => write!(f, "DEP"),
+            FrequencyType::Arrival => write!(f, "ARR"),
+            FrequencyType::ApproachDeparture => write!(f, "APP/DEP"),
+            FrequencyType::ArrivalDeparture => write!(f, "ARR/DEP"),
+            FrequencyType::Tower => write!(f, "TWR"),
+            FrequencyType::Ground => write!(f, "GND"),
+            FrequencyType::Com => write!(f, "COM"),
+            FrequencyType::Clearance => write!(f, "CLR"),
+            FrequencyType::Emergency => write!(f, "EMR"),
+            FrequencyType::Atis => write!(f, "ATIS"),
+            FrequencyType::Afis => write!(f, "AFIS"),
+            FrequencyType::AutoInformation => write!(f, "AUTO INFORMATION"),
+            FrequencyType::SafetyComm => write!(f, "SAFETY COMM"),
+            FrequencyType::Info => write!(f, "INFO"),
+            FrequencyType::Ctaf => write!(f, "CTAF"),
+            FrequencyType::Arcal => write!(f, "ARCAL"),
+            FrequencyType::Unic => write!(f, "UNIC"),
+            FrequencyType::Unicom => write!(f, "UNICOM"),
+            FrequencyType::Cntr => write!(f, "CNTR"),
+            FrequencyType::Artc => write!(f, "ARTC"),
+            FrequencyType::AD => write!(f, "A/D"),
+            FrequencyType::AG => write!(f, "A/G"),
+            FrequencyType::Mult => write!(f, "MULT"),
+            FrequencyType::Ops => write!(f, "OPS"),
+            FrequencyType::Atf => write!(f, "ATF"),
+            FrequencyType::Acc => write!(f, "ACC"),
+            FrequencyType::Misc => write!(f, "MISC"),
+            FrequencyType::Pmsv => write!(f, "PMSV"),
+            FrequencyType::Traffic => write!(f, "TFC"),
+            FrequencyType::Awos => write!(f, "AWOS"),
+            FrequencyType::Asos => write!(f, "ASOS"),
+            FrequencyType::Fss => write!(f, "FSS"),
+            FrequencyType::Fcc => write!(f, "FCC"),
+            FrequencyType::Rdo => write!(f, "RDO"),
+            FrequencyType::Rco => write!(f, "RCO"),
+            FrequencyType::Cld => write!(f, "CLD"),
+            FrequencyType::Fia => write!(f, "FIA"),
+            FrequencyType::Apron => write!(f, "APRON"),
+            FrequencyType::Ramp => write!(f, "RMP"),
+            FrequencyType::Military => write!(f, "MIL"),
+            FrequencyType::Ils => write!(f, "ILS"),
+            FrequencyType::Radar => write!(f, "RADAR"),
+            FrequencyType::Private => write!(f, "Private"),
+            FrequencyType::NotControlled => write!(f, "Non controlled"),
+            FrequencyType::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+impl FromStr for FrequencyType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<FrequencyType, Self::Err> {
+        let input = input.to_string().to_uppercase();
+        match input.as_str() {
+            "APP" => Ok(FrequencyType::Approach),
+            "APR" => Ok(FrequencyType::Approach),
+            "APPR" => Ok(FrequencyType::Approach),
+            "APROAC" => Ok(FrequencyType::Approach),
+            "APPROACH" => Ok(FrequencyType::Approach),
+            "TMA" => Ok(FrequencyType::Approach),
+            "APP/DEP" => Ok(FrequencyType::ApproachDeparture),
+            "ARR/DEP" => Ok(FrequencyType::ArrivalDeparture),
+            "DEP" => Ok(FrequencyType::Departure),
+            "DEPARTURES" => Ok(FrequencyType::Departure),
+            "ARR" => Ok(FrequencyType::Arrival),
+            "TWR" => Ok(FrequencyType::Tower),
+            "TOWER" => Ok(FrequencyType::Tower),
+            "EMR" => Ok(FrequencyType::Emergency),
+            "EMERG" => Ok(FrequencyType::Emergency),
+            "EMEG" => Ok(FrequencyType::Emergency),
+            "EMG" => Ok(FrequencyType::Emergency),
+            "CLR" => Ok(FrequencyType::Clearance),
+            "CLEARANCE" => Ok(FrequencyType::Clearance),
+            "GND" => Ok(FrequencyType::Ground),
+            "GROUND" => Ok(FrequencyType::Ground),
+            "GRN" => Ok(FrequencyType::Ground),
+            "COM" => Ok(FrequencyType::Com),
+            "ATIS" => Ok(FrequencyType::Atis),
+            "AFIS" => Ok(FrequencyType::Afis),
+            "AUTO INFO" => Ok(FrequencyType::AutoInformation),
+            "AUTOINFO" => Ok(FrequencyType::AutoInformation),
+            "AUTO-INFO" => Ok(FrequencyType::AutoInformation),
+            "A/A" => Ok(FrequencyType::AutoInformation),
+            "AUTO INFORMATION" => Ok(FrequencyType::AutoInformation),
+            "AUTO INFORMACION" => Ok(FrequencyType::AutoInformation),
+            "SAFETY COMM" => Ok(FrequencyType::SafetyComm),
+            "SAFETYCOM" => Ok(FrequencyType::SafetyComm),
+            "SAFETY COM" => Ok(FrequencyType::SafetyComm),
+            "FIS" => Ok(FrequencyType::Afis),
+            "INFO" => Ok(FrequencyType::Info),
+            "SIV" => Ok(FrequencyType::Info),
+            "CTAF" => Ok(FrequencyType::Ctaf),
+            "ARCAL" => Ok(FrequencyType::Arcal),
+            "UNIC" => Ok(FrequencyType::Unic),
+            "UNICOM" => Ok(FrequencyType::Unicom),
+            "CNTR" => Ok(FrequencyType::Cntr),
+            "CENTER" => Ok(FrequencyType::Cntr),
+            "ARTC" => Ok(FrequencyType::Artc),
+            "A/D" => Ok(FrequencyType::AD),
+            "A/G" => Ok(FrequencyType::AG),
+            "MULT" => Ok(FrequencyType::Mult),
+            "OPS" => Ok(FrequencyType::Ops),
+            "ATF" => Ok(FrequencyType::Atf),
+            "ACC" => Ok(FrequencyType::Acc),
+            "MISC" => Ok(FrequencyType::Misc),
+            "PMSV" => Ok(FrequencyType::Pmsv),
+            "TFC" => Ok(FrequencyType::Traffic),
+            "TRFC" => Ok(FrequencyType::Traffic),
+            "AWOS" => Ok(FrequencyType::Awos),
+            "ASOS" => Ok(FrequencyType::Asos),
+            "FSS" => Ok(FrequencyType::Fss),
+            "FCC" => Ok(FrequencyType::Fcc),
+            "RDO" => Ok(FrequencyType::Rdo),
+            "RCO" => Ok(FrequencyType::Rco),
+            "CLD" => Ok(FrequencyType::Cld),
+            "FIA" => Ok(FrequencyType::Fia),
+            "RMP" => Ok(FrequencyType::Ramp),
+            "APRON" => Ok(FrequencyType::Apron),
+            "MIL" => Ok(FrequencyType::Military),
+            "ILS" => Ok(FrequencyType::Ils),
+            "RAD" => Ok(FrequencyType::Radar),
+            "RDR" => Ok(FrequencyType::Radar),
+            "RADAR" => Ok(FrequencyType::Radar),
+            "PRIVATE" => Ok(FrequencyType::Private),
+            "NON CONTROLLED" => Ok(FrequencyType::NotControlled),
+            _ => {
+                error!("Unknow frequency type {}", input);
+                Ok(FrequencyType::Unknown)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub enum NavaidType {
+    Vor,
+    VorDme,
+    Dme,
+    Adf,
+    VorTac,
+    Tacan,
+    Ndb,
+    NdbDme,
+    #[default]
+    Unknown,
+}
+
+impl fmt::Display for NavaidType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NavaidType::Vor => write!(f, "VOR"),
+            NavaidType::VorDme => write!(f, "VOR-DME"),
+            NavaidType::Dme => write!(f, "DME"),
+            NavaidType::VorTac => write!(f, "VORTAC"),
+            NavaidType::Tacan => write!(f, "TACAN"),
+            NavaidType::Adf => write!(f, "ADF"),
+            NavaidType::Ndb => write!(f, "NDB"),
+            NavaidType::NdbDme => write!(f, "NDB-DME"),
+            NavaidType::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+impl FromStr for NavaidType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<NavaidType, Self::Err> {
+        match input {
+            "VOR" => Ok(NavaidType::Vor),
+            "VOR-DME" => Ok(NavaidType::VorDme),
+            "DME" => Ok(NavaidType::Dme),
+            "VORTAC" => Ok(NavaidType::VorTac),
+            "ADF" => Ok(NavaidType::Adf),
+            "NDB" => Ok(NavaidType::Ndb),
+            "NDB-DME" => Ok(NavaidType::NdbDme),
+            "TACAN" => Ok(NavaidType::Tacan),
+            _ => {
+                error!("Unknow navaid type {}", input);
+                Ok(NavaidType::Unknown)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct LocationPoint {
+    r#type: LocationType,
+    coordinates: Vec<f64>,
+}
+
+impl LocationPoint {
+    pub fn latitude(&self) -> f64 {
+        self.coordinates.get(1).copied().unwrap_or_default()
+    }
+
+    pub fn longitude(&self) -> f64 {
+        self.coordinates.first().copied().unwrap_or_default()
+    }
+
+    /// Great-circle distance to the given point, in nautical miles
+    pub fn distance_nm(&self, latitude: f64, longitude: f64) -> f64 {
+        great_circle_distance_nm(self.latitude(), self.longitude(), latitude, longitude)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub enum LocationType {
+    #[default]
+    Point,
+}
+
+impl fmt::Display for LocationType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LocationType::Point => write!(f, "Point"),
+        }
+    }
+}
+impl FromStr for LocationType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<LocationType, Self::Err> {
+        match input {
+            "Point" => Ok(LocationType::Point),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct Airport {
+    pub id: i64,
+    pub icao_code: String,
+    pub r#type: AirportType,
+    pub name: String,
+    pub location: LocationPoint,
+    pub elevation_ft: i64,
+    pub continent: String,
+    pub iso_country: String,
+    pub iso_region: String,
+    pub municipality: String,
+    pub scheduled_service: String,
+    pub gps_code: String,
+    pub iata_code: String,
+    pub local_code: String,
+    pub home_link: String,
+    pub wikipedia_link: String,
+    pub keywords: String,
+    #[serde(default)]
+    pub runways: Vec<Runway>,
+    #[serde(default)]
+    pub frequencies: Vec<Frequency>,
+    #[serde(default)]
+    pub navaids: Vec<Navaid>,
+    /// Distance to the searched point, in nautical miles. Only set on proximity searchs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance_nm: Option<f64>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct Runway {
+    pub id: i64,
+    pub airport_id: i64,
+    pub airport_icao_code: String,
+    pub length_ft: i64,
+    pub width_ft: i64,
+    pub surface: i64,
+    pub lighted: i64,
+    pub closed: i64,
+    pub le_ident: String,
+    pub le_location: LocationPoint,
+    pub le_elevation_ft: i64,
+    pub le_heading_deg_t: i64,
+    pub le_displaced_threshold_ft: i64,
+    pub he_ident: String,
+    pub he_location: LocationPoint,
+    pub he_elevation_ft: i64,
+    pub he_heading_deg_t: i64,
+    pub he_displaced_threshold_ft: i64,
+}
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct Frequency {
+    pub id: i64,
+    pub airport_id: i64,
+    pub airport_icao_code: String,
+    pub r#type: FrequencyType,
+    pub raw_type: String,
+    pub description: String,
+    pub frequency_mhz: f64,
+}
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct Navaid {
+    pub id: i64,
+    pub filename: String,
+    pub icao_code: String,
+    pub name: String,
+    pub r#type: NavaidType,
+    pub frequency_khz: i64,
+    pub location: LocationPoint,
+    pub elevation_ft: i64,
+    pub iso_country: String,
+    pub dme_frequency_khz: i64,
+    pub dme_channel: String,
+    pub dme_location: LocationPoint,
+    pub dme_elevation_ft: i64,
+    pub slaved_variation_deg: i64,
+    pub magnetic_variation_deg: i64,
+    pub usage_type: String,
+    pub power: String,
+    pub associated_airport: String,
+    /// Distance to the searched point, in nautical miles. Only set on proximity searchs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub distance_nm: Option<f64>,
+}
+
+/// Area delimited by two meridians and two parallels. When `min_lon` is greater
+/// than `max_lon`, the box crosses the antimeridian
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+impl BoundingBox {
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.min_lon > self.max_lon
+    }
+}
+
+impl FromStr for BoundingBox {
+    type Err = String;
+
+    /// Parses a `minLon,minLat,maxLon,maxLat` string
+    fn from_str(input: &str) -> Result<BoundingBox, Self::Err> {
+        let values = input
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| "Bounding box values must be numbers".to_string())?;
+        let [min_lon, min_lat, max_lon, max_lat] = values[..] else {
+            return Err("Bounding box must be minLon,minLat,maxLon,maxLat".to_string());
+        };
+        if !(-180.0..=180.0).contains(&min_lon) || !(-180.0..=180.0).contains(&max_lon) {
+            return Err("Bounding box longitudes must be between -180 and 180".to_string());
+        }
+        if !(-90.0..=90.0).contains(&min_lat) || !(-90.0..=90.0).contains(&max_lat) {
+            return Err("Bounding box latitudes must be between -90 and 90".to_string());
+        }
+        if min_lat > max_lat {
+            return Err("Bounding box minLat must be lower than maxLat".to_string());
+        }
+        Ok(BoundingBox {
+            min_lon,
+            min_lat,
+            max_lon,
+            max_lat,
+        })
+    }
+}
+
+/// Search criteria shared by airport and navaid searchs
+#[derive(Clone, Debug, Default)]
+pub struct SearchFilter {
+    pub search: Option<String>,
+    pub page: Option<u64>,
+    /// Number of results per page, capped to `MAX_PAGE_SIZE`
+    pub limit: Option<u64>,
+    pub country: Option<String>,
+    /// Airport or navaid type
+    pub r#type: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Maximum distance to the searched point. Defaults to `DEFAULT_SEARCH_RADIUS_NM`
+    pub radius_nm: Option<f64>,
+    pub bbox: Option<BoundingBox>,
+    /// Airports are returned without their runways, frequencies and navaids
+    pub skip_details: bool,
+}
+
+impl SearchFilter {
+    /// Searched point as (latitude, longitude), if any
+    pub fn center(&self) -> Option<(f64, f64)> {
+        self.latitude.zip(self.longitude)
+    }
+
+    pub fn radius_nm(&self) -> f64 {
+        self.radius_nm.unwrap_or(DEFAULT_SEARCH_RADIUS_NM)
+    }
+
+    pub fn page_size(&self) -> u64 {
+        self.limit.unwrap_or(MAX_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// Number of results skipped. `validate` rejects pages whose offset would overflow
+    pub fn offset(&self) -> u64 {
+        self.page.unwrap_or(0).saturating_mul(self.page_size())
+    }
+
+    /// Checks the consistency of the criteria, returning a description of the first error found
+    pub fn validate(&self) -> Result<(), String> {
+        if self.latitude.is_some() != self.longitude.is_some() {
+            return Err("latitude and longitude must be given together".to_string());
+        }
+        if let Some((latitude, longitude)) = self.center() {
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                return Err("latitude or longitude out of range".to_string());
+            }
+        }
+        if let Some(radius_nm) = self.radius_nm {
+            if self.center().is_none() {
+                return Err("radius_nm requires latitude and longitude".to_string());
+            }
+            if radius_nm.is_nan() || radius_nm <= 0.0 {
+                return Err("radius_nm must be positive".to_string());
+            }
+        }
+        if let Some(limit) = self.limit {
+            if limit == 0 || limit > MAX_PAGE_SIZE {
+                return Err(format!("limit must be between 1 and {}", MAX_PAGE_SIZE));
+            }
+        }
+        if let Some(page) = self.page {
+            // Offsets are passed to the databases as signed 64-bit integers
+            let offset = page.checked_mul(self.page_size());
+            if offset.filter(|offset| *offset <= i64::MAX as u64).is_none() {
+                return Err("page is too large".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct AppState {
+    pub database: Box<dyn NavDataStore>,
+    /// Where the data is refreshed from
+    pub source: DataSource,
+    pub config: Config,
+    /// Version of the dataset currently served, computed on first use after a refresh
+    pub meta: RwLock<Option<DatasetMeta>>,
+    /// Incremented on each refresh, under the `meta` lock, so that versions computed
+    /// from the previous dataset are not cached
+    generation: AtomicU64,
+}
+
+impl AppState {
+    pub fn new(database: Box<dyn NavDataStore>, source: DataSource, config: Config) -> AppState {
+        AppState {
+            database,
+            source,
+            config,
+            meta: RwLock::new(None),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub async fn refresh(&self) -> Result<(), UpdateError> {
+        self.refresh_from(&self.source).await
+    }
+
+    /// Reloads the data from another source than the configured one, such as a snapshot
+    pub async fn refresh_from(&self, source: &DataSource) -> Result<(), UpdateError> {
+        let result = self.database.refresh(source).await;
+        let mut meta = self.meta.write().expect(ERROR_DATASET_META_ACCESS);
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        *meta = None;
+        result
+    }
+
+    pub async fn dataset_meta(&self) -> Result<DatasetMeta, Box<dyn Error>> {
+        if let Some(meta) = self.meta.read().expect(ERROR_DATASET_META_ACCESS).as_ref() {
+            return Ok(meta.clone());
+        }
+        let generation = self.generation.load(Ordering::SeqCst);
+        let meta = DatasetMeta::from(self.database.loaded_files().await?);
+        let mut cached = self.meta.write().expect(ERROR_DATASET_META_ACCESS);
+        // A refresh ended while the files were read, they may belong to the previous dataset
+        if self.generation.load(Ordering::SeqCst) == generation {
+            *cached = Some(meta.clone());
+        }
+        Ok(meta)
+    }
+}
+
+/// Refreshes the data following the schedule. `startup_load` is the outcome of
+/// the startup load if it already happened, otherwise data is loaded right away
+pub async fn periodical_update(
+    app_state: Arc<AppState>,
+    schedule: Schedule,
+    startup_load: Option<Result<(), UpdateError>>,
+) {
+    let mut result = match startup_load {
+        Some(result) => result,
+        None => app_state.refresh().await,
+    };
+    loop {
+        let Some(delay) = schedule.next_delay(result.is_err()) else {
+            info!("Automatic data refresh is disabled");
+            return;
+        };
+        info!("Next data refresh in {}s", delay.as_secs());
+        sleep(delay).await;
+        info!("Awake ! reloading data");
+        result = app_state.refresh().await;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum BackendType {
+    #[default]
+    SQLITE,
+    MONGODB,
+    POSTGRES,
+    MEMORY,
+}
+
+impl FromStr for BackendType {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<BackendType, Self::Err> {
+        match input.to_uppercase().as_str() {
+            "SQLITE" => Ok(BackendType::SQLITE),
+            "MONGODB" => Ok(BackendType::MONGODB),
+            "POSTGRES" => Ok(BackendType::POSTGRES),
+            "MEMORY" => Ok(BackendType::MEMORY),
+            _ => Err(format!(
+                "Unknown backend {}, expected SQLITE, MONGODB, POSTGRES or MEMORY",
+                input
+            )),
+        }
+    }
+}
+
+/// Storage backend of the navigation data. New backends, including ones defined
+/// in other crates, only have to implement this trait
+#[async_trait(?Send)]
+pub trait NavDataStore: Send + Sync {
+    async fn get_airport_by_icao_code(
+        &self,
+        icao: String,
+    ) -> Result<Option<Airport>, Box<dyn Error>>;
+
+    async fn get_navaids_by_icao_code(&self, icao: String) -> Result<Vec<Navaid>, Box<dyn Error>>;
+
+    async fn search_airport(&self, filter: SearchFilter) -> Result<Vec<Airport>, Box<dyn Error>>;
+
+    async fn search_navaid(&self, filter: SearchFilter) -> Result<Vec<Navaid>, Box<dyn Error>>;
+
+    /// Loads the data from the data source, recording the outcome of each file in
+    /// the update status
+    async fn load(&self, source: &DataSource) -> Result<(), UpdateError>;
+
+    /// Data files currently loaded
+    async fn loaded_files(&self) -> Result<Vec<LoadedFile>, Box<dyn Error>>;
+
+    fn update_status(&self) -> &Mutex<UpdateStatus>;
+
+    /// Reloads the data and records the outcome. Fails if a refresh is already running
+    async fn refresh(&self, source: &DataSource) -> Result<(), UpdateError> {
+        let cycle = start_cycle(self.update_status())?;
+        info!("Reloading data");
+        let result = match source.decoded() {
+            Ok(source) => self.load(&source).await,
+            Err(err) => Err(UpdateError::Source(err.to_string())),
+        };
+        end_of_cycle(cycle, &result);
+        result
+    }
+
+    fn is_refreshing(&self) -> bool {
+        self.update_status()
+            .lock()
+            .expect(ERROR_UPDATE_STATUS_ACCESS)
+            .running
+    }
+
+    /// Outcome of the data updates, with the files currently loaded
+    async fn refresh_status(&self) -> Result<UpdateStatus, Box<dyn Error>> {
+        let loaded = self.loaded_files().await?;
+        Ok(self
+            .update_status()
+            .lock()
+            .expect(ERROR_UPDATE_STATUS_ACCESS)
+            .report(loaded))
+    }
+}
+
+/// Opens the backend of the given type
+pub async fn open_backend(backend_type: BackendType, path: String) -> Box<dyn NavDataStore> {
+    match backend_type {
+        BackendType::MONGODB => Box::new(MongoDbBackend::new(path.as_str()).await),
+        BackendType::SQLITE => Box::new(SqliteBackend::new(path)),
+        BackendType::POSTGRES => Box::new(PostgresBackend::new(path.as_str()).await),
+        BackendType::MEMORY => Box::new(MemoryBackend::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    fn page(page: Option<u64>, limit: Option<u64>) -> SearchFilter {
+        SearchFilter {
+            page,
+            limit,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pages_default_to_the_first_and_the_maximum_size() {
+        let filter = page(None, None);
+        assert_eq!(filter.offset(), 0);
+        assert_eq!(filter.page_size(), MAX_PAGE_SIZE);
+        assert!(filter.validate().is_ok());
+    }
+
+    #[test]
+    fn offset_is_the_page_times_the_page_size() {
+        assert_eq!(page(Some(2), Some(10)).offset(), 20);
+        assert_eq!(page(Some(3), None).offset(), 3 * MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn limit_must_be_between_one_and_the_maximum_page_size() {
+        assert!(page(None, Some(0)).validate().is_err());
+        assert!(page(None, Some(1)).validate().is_ok());
+        assert!(page(None, Some(MAX_PAGE_SIZE)).validate().is_ok());
+        assert!(page(None, Some(MAX_PAGE_SIZE + 1)).validate().is_err());
+    }
+
+    #[test]
+    fn pages_whose_offset_overflows_are_rejected() {
+        let last_page = i64::MAX as u64 / MAX_PAGE_SIZE;
+        assert!(page(Some(last_page), None).validate().is_ok());
+        assert!(page(Some(last_page + 1), None).validate().is_err());
+        assert!(page(Some(u64::MAX), Some(2)).validate().is_err());
+        assert!(page(Some(i64::MAX as u64), Some(1)).validate().is_ok());
+        assert_eq!(page(Some(u64::MAX), None).offset(), u64::MAX);
+    }
+
+    #[test]
+    fn bounding_box_is_parsed_from_min_lon_min_lat_max_lon_max_lat() {
+        let bbox = "-5,42,8,51".parse::<BoundingBox>().unwrap();
+        assert_eq!(
+            bbox,
+            BoundingBox {
+                min_lon: -5.0,
+                min_lat: 42.0,
+                max_lon: 8.0,
+                max_lat: 51.0,
+            }
+        );
+        assert!(!bbox.crosses_antimeridian());
+    }
+
+    #[test]
+    fn bounding_box_with_min_lon_above_max_lon_crosses_the_antimeridian() {
+        let bbox = "170,-10,-170,10".parse::<BoundingBox>().unwrap();
+        assert!(bbox.crosses_antimeridian());
+    }
+
+    #[test]
+    fn invalid_bounding_boxes_are_rejected() {
+        assert!("1,2,3".parse::<BoundingBox>().is_err());
+        assert!("a,2,3,4".parse::<BoundingBox>().is_err());
+        assert!("-181,0,10,10".parse::<BoundingBox>().is_err());
+        assert!("0,-91,10,10".parse::<BoundingBox>().is_err());
+        assert!("0,20,10,10".parse::<BoundingBox>().is_err());
+    }
+
+    #[test]
+    fn great_circle_distance_of_a_point_to_itself_is_zero() {
+        assert_eq!(great_circle_distance_nm(49.0, 2.5, 49.0, 2.5), 0.0);
+    }
+
+    #[test]
+    fn great_circle_distance_of_one_degree_of_latitude_is_sixty_nm() {
+        assert_close(great_circle_distance_nm(45.0, 5.0, 46.0, 5.0), 60.0, 0.1);
+    }
+
+    #[test]
+    fn great_circle_distance_between_lfpg_and_kjfk() {
+        let distance = great_circle_distance_nm(49.0097, 2.5479, 40.6398, -73.7789);
+        assert_close(distance, 3150.0, 10.0);
+    }
+
+    #[test]
+    fn great_circle_distance_across_the_antimeridian() {
+        assert_close(great_circle_distance_nm(0.0, 179.5, 0.0, -179.5), 60.0, 0.1);
+    }
+
+    #[test]
+    fn great_circle_distance_between_antipodes() {
+        let half_circumference = std::f64::consts::PI * EARTH_RADIUS_NM;
+        assert_close(
+            great_circle_distance_nm(90.0, 0.0, -90.0, 0.0),
+            half_circumference,
+            0.1,
+        );
+    }
+}
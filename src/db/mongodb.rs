@@ -0,0 +1,661 @@
+use super::update::{with_retry, LoadedFile, UpdateError, UpdateStatus};
+use super::{
+    source::DataSource, sqlite::SqliteBackend, Airport, AirportType, NavDataStore, Navaid,
+    NavaidType, SearchFilter, AIRPORT_CSV, CSV_FILES, METERS_PER_NM, NAVAID_CSV,
+};
+use crate::messages::ERROR_UPDATE_STATUS_ACCESS;
+use async_trait::async_trait;
+use bson::{doc, Document};
+use futures::stream::TryStreamExt;
+use log::{error, info};
+use mongodb::{
+    options::{ClientOptions, FindOptions, IndexOptions, ReplaceOptions, Sphere2DIndexVersion},
+    Client, Collection, IndexModel,
+};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+const APP_NAME: &str = "navdata";
+const DATABASE_NAME: &str = "navdata";
+const AIRPORTS_COLLECTION: &str = "airports";
+const NAVAIDS_COLLECTION: &str = "navaids";
+const DATA_LAST_UPDATE_COLLECTION: &str = "data_last_update";
+
+pub struct MongoDbBackend {
+    client: Client,
+    status: Mutex<UpdateStatus>,
+}
+
+impl MongoDbBackend {
+    pub async fn new(database_adress: &str) -> MongoDbBackend {
+        let mut client_options = ClientOptions::parse(database_adress).await.unwrap();
+        client_options.app_name = Some(APP_NAME.to_string());
+
+        // Get a handle to the deployment.
+        let client = Client::with_options(client_options).unwrap();
+
+        // List the names of the databases in that deployment.
+        info!("Check if database {}  exists", DATABASE_NAME);
+        let mut database_exists = false;
+        for db_name in client.list_database_names(None, None).await.unwrap() {
+            println!("{}", db_name);
+            if db_name == DATABASE_NAME {
+                database_exists = true;
+                break;
+            }
+        }
+        if !database_exists {
+            panic!("{} database does not exists", DATABASE_NAME);
+        }
+        info!("Found database {}. Continue", DATABASE_NAME);
+
+        let backend = MongoDbBackend {
+            client: client.clone(),
+            status: Mutex::new(UpdateStatus::default()),
+        };
+        backend.create_collections().await;
+        backend.create_indexes().await;
+        backend
+    }
+
+    async fn create_collections(&self) {
+        // check if collections exists, create if non
+        let coll_list: Vec<String> = self
+            .client
+            .database(DATABASE_NAME)
+            .list_collection_names(doc! {})
+            .await
+            .unwrap();
+        println!("{:?}", coll_list);
+        if !coll_list.contains(&AIRPORTS_COLLECTION.to_string()) {
+            info!(
+                "Collection {} does not exists. Creating...",
+                AIRPORTS_COLLECTION
+            );
+            self.client
+                .database(DATABASE_NAME)
+                .create_collection(AIRPORTS_COLLECTION, None)
+                .await
+                .unwrap();
+        }
+        if !coll_list.contains(&NAVAIDS_COLLECTION.to_string()) {
+            info!(
+                "Collection {} does not exists. Creating...",
+                NAVAIDS_COLLECTION
+            );
+            self.client
+                .database(DATABASE_NAME)
+                .create_collection(NAVAIDS_COLLECTION, None)
+                .await
+                .unwrap();
+        }
+    }
+
+    async fn create_indexes(&self) {
+        // https://www.mongodb.com/docs/drivers/rust/current/fundamentals/indexes/
+
+        let airports_collection: Collection<Airport> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(AIRPORTS_COLLECTION);
+        let navaids_collection: Collection<Navaid> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(NAVAIDS_COLLECTION);
+
+        // id must be unique on all collections for updates
+        let option = IndexOptions::builder().unique(true).build();
+        let index_model = IndexModel::builder()
+            .keys(doc! { "id": 1 })
+            .options(option)
+            .build();
+        let index = airports_collection
+            .create_index(index_model.clone(), None)
+            .await
+            .unwrap();
+        info!("Index {} created for airports collection", index.index_name);
+        let index = navaids_collection
+            .create_index(index_model, None)
+            .await
+            .unwrap();
+        info!("Index {} created for navaid collection", index.index_name);
+
+        // icao_code are unique for airports must be unique on all collections for updates
+        let option = IndexOptions::builder().unique(true).build();
+        let index_model = IndexModel::builder()
+            .keys(doc! { "iaco_code": "text" })
+            .options(option)
+            .build();
+        let index = airports_collection
+            .create_index(index_model.clone(), None)
+            .await
+            .unwrap();
+        info!("Index {} created for airports collection", index.index_name);
+
+        // name, municipality, iata_code, iso_country and type are mandatory to speed_up searchs on airports
+        let index_model = IndexModel::builder().keys(doc! { "name.$**": 1  }).build();
+        match airports_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for airports collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+        let index_model = IndexModel::builder()
+            .keys(doc! { "municipality.$**": 1 })
+            .build();
+        match airports_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for airports collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+        let index_model = IndexModel::builder()
+            .keys(doc! { "iata_code.$**": 1 })
+            .build();
+        match airports_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for airports collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+        let index_model = IndexModel::builder()
+            .keys(doc! {  "iso_country.$**": 1  })
+            .build();
+        match airports_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for airports collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+        let index_model = IndexModel::builder().keys(doc! { "type.$**": 1  }).build();
+        match airports_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for airports collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+
+        // name, filename, associated_airport, iso_country and type are mandatory to speed_up searchs on navaids
+        let index_model = IndexModel::builder().keys(doc! { "name.$**": 1 }).build();
+        match navaids_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for navaids collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+        let index_model = IndexModel::builder()
+            .keys(doc! { "filename.$**": 1 })
+            .build();
+        match navaids_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for navaids collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+        let index_model = IndexModel::builder()
+            .keys(doc! { "associated_airport.$**": 1,"type.$**": 1 })
+            .build();
+        match navaids_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for navaids collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+        let index_model = IndexModel::builder().keys(doc! {"type.$**": 1 }).build();
+        match navaids_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for navaids collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+        let index_model = IndexModel::builder()
+            .keys(doc! {"iso_country.$**": 1 })
+            .build();
+        match navaids_collection
+            .create_index(index_model.clone(), None)
+            .await
+        {
+            Ok(index) => info!("Index {} created for navaids collection", index.index_name),
+            Err(err) => info!("Index not created, may alreay exists : {}", err),
+        }
+
+        // locations are geo-indexed
+        let option = IndexOptions::builder()
+            .sphere_2d_index_version(Sphere2DIndexVersion::V3)
+            .build();
+        let index_model = IndexModel::builder()
+            .keys(doc! { "location": "2dsphere" })
+            .options(option)
+            .build();
+        let index = airports_collection
+            .create_index(index_model.clone(), None)
+            .await
+            .unwrap();
+        info!("Index {} created for airports collection", index.index_name);
+        let index = navaids_collection
+            .create_index(index_model, None)
+            .await
+            .unwrap();
+        info!("Index {} created for navaid collection", index.index_name);
+    }
+
+    async fn record_loaded_file(&self, loaded_file: &LoadedFile) -> Result<(), Box<dyn Error>> {
+        let collection: Collection<LoadedFile> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(DATA_LAST_UPDATE_COLLECTION);
+        let option = ReplaceOptions::builder().upsert(true).build();
+        collection
+            .replace_one(
+                doc! { "file": &loaded_file.file },
+                loaded_file,
+                Some(option),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Downloads a data file and loads it into the temporary SQLite database
+    async fn stage_file(
+        &self,
+        source: &DataSource,
+        sqlite_be: &SqliteBackend,
+        file: &str,
+        sha: Option<&String>,
+    ) -> Result<Option<String>, UpdateError> {
+        let sha = sha.ok_or_else(|| UpdateError::MissingFile(file.to_string()))?;
+        let data = with_retry(&format!("Downloading {}", file), || source.fetch(file))
+            .await
+            .map_err(|reason| UpdateError::Fetch {
+                file: file.to_string(),
+                reason,
+            })?;
+        sqlite_be
+            .load_csv(file, data, Some(sha.clone()))
+            .await
+            .map_err(|err| UpdateError::Load {
+                file: file.to_string(),
+                reason: err.to_string(),
+            })?;
+        Ok(Some(sha.clone()))
+    }
+
+    // Copying airports from sqlite to mongodb
+    async fn copy_airports(&self, sqlite_be: &SqliteBackend) -> Result<(), Box<dyn Error>> {
+        let airports_collection: Collection<Airport> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(AIRPORTS_COLLECTION);
+        info!("Start adding airports");
+        let mut airport_count = 0;
+        let mut page = 0;
+        loop {
+            let airports = sqlite_be
+                .search_airport(SearchFilter {
+                    page: Some(page),
+                    ..Default::default()
+                })
+                .await?;
+
+            if airports.is_empty() {
+                break;
+            }
+
+            for airport in airports {
+                if airport.icao_code.len() < 4 {
+                    continue;
+                }
+                let option = ReplaceOptions::builder().upsert(true).build();
+                airports_collection
+                    .replace_one(doc! { "id": airport.id }, airport, Some(option))
+                    .await?;
+                airport_count += 1;
+            }
+            page += 1
+        }
+        info!("{} airports added to MongoDB", airport_count);
+        Ok(())
+    }
+
+    // Copying navaids from sqlite to mongodb
+    async fn copy_navaids(&self, sqlite_be: &SqliteBackend) -> Result<(), Box<dyn Error>> {
+        let navaids_collection: Collection<Navaid> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(NAVAIDS_COLLECTION);
+        info!("Start adding navaid");
+        let mut navaid_count = 0;
+        let mut page = 0;
+        loop {
+            let navaids = sqlite_be
+                .search_navaid(SearchFilter {
+                    page: Some(page),
+                    ..Default::default()
+                })
+                .await?;
+
+            if navaids.is_empty() {
+                break;
+            }
+            for navaid in navaids {
+                let option = ReplaceOptions::builder().upsert(true).build();
+                navaids_collection
+                    .replace_one(doc! { "id": navaid.id }, navaid, Some(option))
+                    .await?;
+                navaid_count += 1;
+            }
+            page += 1;
+        }
+        info!("{} navaids added to MongoDB", navaid_count);
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl NavDataStore for MongoDbBackend {
+    async fn get_airport_by_icao_code(
+        &self,
+        icao: String,
+    ) -> Result<Option<Airport>, Box<dyn Error>> {
+        let coll: Collection<Airport> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(AIRPORTS_COLLECTION);
+        let result = coll
+            .find_one(doc! {"icao_code": icao.to_uppercase()}, None)
+            .await?;
+        Ok(result)
+    }
+
+    async fn get_navaids_by_icao_code(&self, icao: String) -> Result<Vec<Navaid>, Box<dyn Error>> {
+        let coll: Collection<Navaid> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(NAVAIDS_COLLECTION);
+        let mut result = coll
+            .find(doc! {"icao_code": icao.to_uppercase()}, None)
+            .await?;
+        let mut navaids = vec![];
+        while let Some(navaid) = result.try_next().await? {
+            navaids.push(navaid);
+        }
+        Ok(navaids)
+    }
+
+    async fn search_airport(&self, filter: SearchFilter) -> Result<Vec<Airport>, Box<dyn Error>> {
+        let coll: Collection<Airport> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(AIRPORTS_COLLECTION);
+
+        let mut ands = search_filters(&filter, &["icao_code", "name", "municipality", "iata_code"]);
+
+        if let Some(airport_type) = &filter.r#type {
+            ands.push(type_filter::<AirportType>(airport_type.to_lowercase()));
+        }
+
+        let query = match ands.len() {
+            0 => None,
+            _ => Some(doc! {"$and":ands}),
+        };
+
+        let projection = filter
+            .skip_details
+            .then(|| doc! {"runways": 0, "frequencies": 0, "navaids": 0});
+        let options = FindOptions::builder()
+            .skip(filter.offset())
+            .limit(filter.page_size() as i64)
+            .projection(projection)
+            .build();
+
+        let mut result = coll.find(query, options).await?;
+        let mut airports = vec![];
+        while let Some(mut airport) = result.try_next().await? {
+            if let Some((latitude, longitude)) = filter.center() {
+                airport.distance_nm = Some(airport.location.distance_nm(latitude, longitude));
+            }
+            airports.push(airport);
+        }
+        Ok(airports)
+    }
+
+    async fn search_navaid(&self, filter: SearchFilter) -> Result<Vec<Navaid>, Box<dyn Error>> {
+        let coll: Collection<Navaid> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(NAVAIDS_COLLECTION);
+
+        let mut ands = search_filters(&filter, &["icao_code", "name", "associated_airport"]);
+
+        if let Some(navaid_type) = &filter.r#type {
+            ands.push(type_filter::<NavaidType>(navaid_type.to_uppercase()));
+        }
+
+        let query = match ands.len() {
+            0 => None,
+            _ => Some(doc! {"$and":ands}),
+        };
+
+        let options = FindOptions::builder()
+            .skip(filter.offset())
+            .limit(filter.page_size() as i64)
+            .build();
+
+        let mut result = coll.find(query, options).await?;
+        let mut navaids = vec![];
+        while let Some(mut navaid) = result.try_next().await? {
+            if let Some((latitude, longitude)) = filter.center() {
+                navaid.distance_nm = Some(navaid.location.distance_nm(latitude, longitude));
+            }
+            navaids.push(navaid);
+        }
+        Ok(navaids)
+    }
+
+    /// Reloads all the data files. A failing file does not prevent the others from
+    /// being updated
+    async fn load(&self, source: &DataSource) -> Result<(), UpdateError> {
+        let shas = with_retry("Listing data files", || source.get_list_of_sha())
+            .await
+            .map_err(UpdateError::Source)?;
+
+        // Loading data to sqlite temporarly
+        let sqlite_be = SqliteBackend::new(":memory:".to_string());
+        let mut results = Vec::new();
+        for file in CSV_FILES {
+            let result = self
+                .stage_file(source, &sqlite_be, file, shas.get(file))
+                .await;
+            results.push((file, result));
+        }
+
+        let copy_error = |file: &str, err: Box<dyn Error>| UpdateError::Load {
+            file: file.to_string(),
+            reason: err.to_string(),
+        };
+        let airports_loaded = results
+            .iter()
+            .any(|(file, result)| *file == AIRPORT_CSV && result.is_ok());
+        if airports_loaded {
+            if let Err(err) = self.copy_airports(&sqlite_be).await {
+                let err = copy_error(AIRPORT_CSV, err);
+                for (file, result) in results.iter_mut() {
+                    if *file != NAVAID_CSV && result.is_ok() {
+                        *result = Err(err.clone());
+                    }
+                }
+            }
+        }
+        let navaids_loaded = results
+            .iter()
+            .any(|(file, result)| *file == NAVAID_CSV && result.is_ok());
+        if navaids_loaded {
+            if let Err(err) = self.copy_navaids(&sqlite_be).await {
+                let err = copy_error(NAVAID_CSV, err);
+                for (file, result) in results.iter_mut() {
+                    if *file == NAVAID_CSV {
+                        *result = Err(err.clone());
+                    }
+                }
+            }
+        }
+
+        // Recording the loaded files
+        let loaded_files = sqlite_be.loaded_files().await.unwrap_or_default();
+        for (file, result) in results.iter_mut() {
+            let loaded_file = loaded_files.iter().find(|loaded| loaded.file == *file);
+            if let (Ok(_), Some(loaded_file)) = (&result, loaded_file) {
+                if let Err(err) = self.record_loaded_file(loaded_file).await {
+                    *result = Err(copy_error(file, err));
+                }
+            }
+        }
+
+        let mut status = self.status.lock().expect(ERROR_UPDATE_STATUS_ACCESS);
+        let mut cycle_result = Ok(());
+        for (file, result) in results {
+            if let Err(err) = &result {
+                error!("{}", err);
+                cycle_result = Err(err.clone());
+            }
+            status.record_file(file, &result);
+        }
+        cycle_result
+    }
+
+    /// Data files loaded in the database, as recorded at load time
+    async fn loaded_files(&self) -> Result<Vec<LoadedFile>, Box<dyn Error>> {
+        let collection: Collection<LoadedFile> = self
+            .client
+            .database(DATABASE_NAME)
+            .collection(DATA_LAST_UPDATE_COLLECTION);
+        let cursor = collection.find(None, None).await?;
+        Ok(cursor.try_collect().await?)
+    }
+
+    fn update_status(&self) -> &Mutex<UpdateStatus> {
+        &self.status
+    }
+}
+
+/// Filters of the criteria shared by all searchs. Text search is done on the given fields
+fn search_filters(filter: &SearchFilter, search_fields: &[&str]) -> Vec<Document> {
+    let mut ands = vec![];
+
+    if let Some(search) = &filter.search {
+        let ors = search_fields
+            .iter()
+            .map(|field| doc! {*field:{"$regex" : escape_regex(search), "$options" : "i"}})
+            .collect::<Vec<Document>>();
+        ands.push(doc! {"$or": ors});
+    }
+
+    if let Some((latitude, longitude)) = filter.center() {
+        let geo_filter = doc! {"location":{
+          "$nearSphere": {
+             "$geometry": {
+                "type" : "Point",
+                "coordinates" : [ longitude, latitude ]
+             },
+             "$minDistance": 0,
+             "$maxDistance": filter.radius_nm() * METERS_PER_NM
+          }
+        }};
+        ands.push(geo_filter);
+    }
+
+    if let Some(bbox) = &filter.bbox {
+        // Filtering on raw coordinates keeps parallels as box edges, unlike GeoJSON polygons
+        let lon_filter = match bbox.crosses_antimeridian() {
+            true => doc! {"$or": [
+                {"location.coordinates.0": {"$gte": bbox.min_lon}},
+                {"location.coordinates.0": {"$lte": bbox.max_lon}}
+            ]},
+            false => doc! {"location.coordinates.0": {"$gte": bbox.min_lon, "$lte": bbox.max_lon}},
+        };
+        ands.push(lon_filter);
+        ands.push(doc! {"location.coordinates.1": {"$gte": bbox.min_lat, "$lte": bbox.max_lat}});
+    }
+
+    if let Some(country) = &filter.country {
+        let country_filter = doc! {"iso_country": country.to_uppercase()};
+        ands.push(country_filter);
+    }
+
+    ands
+}
+
+/// Matches the type as stored, under its serde name such as `VorDme` for `VOR-DME`.
+/// Unknown types match nothing, as on the other backends
+fn type_filter<T: FromStr + fmt::Display + Serialize>(r#type: String) -> Document {
+    let stored = T::from_str(&r#type)
+        .ok()
+        .filter(|parsed| parsed.to_string() == r#type)
+        .and_then(|parsed| bson::to_bson(&parsed).ok());
+    match stored {
+        Some(stored) => doc! {"type": stored},
+        None => doc! {"type": {"$in": []}},
+    }
+}
+
+/// Searched text as a regular expression matching it literally
+fn escape_regex(search: &str) -> String {
+    search
+        .chars()
+        .flat_map(|c| match "\\^$.|?*+()[]{}".contains(c) {
+            true => vec!['\\', c],
+            false => vec![c],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn types_are_matched_under_their_stored_name() {
+        assert_eq!(
+            type_filter::<AirportType>("small_airport".to_string()),
+            doc! {"type": "SmallAirport"}
+        );
+        assert_eq!(
+            type_filter::<NavaidType>("VOR-DME".to_string()),
+            doc! {"type": "VorDme"}
+        );
+        assert_eq!(
+            type_filter::<NavaidType>("XYZ".to_string()),
+            doc! {"type": {"$in": []}}
+        );
+    }
+
+    #[test]
+    fn countries_are_matched_in_uppercase() {
+        let filter = SearchFilter {
+            country: Some("fr".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            search_filters(&filter, &["name"]),
+            vec![doc! {"iso_country": "FR"}]
+        );
+    }
+
+    #[test]
+    fn searched_text_is_matched_literally() {
+        assert_eq!(escape_regex("St. John's (NL)"), r"St\. John's \(NL\)");
+        assert_eq!(escape_regex("a+b*c?"), r"a\+b\*c\?");
+    }
+}
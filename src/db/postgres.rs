@@ -0,0 +1,790 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use deadpool_postgres::{Config, Pool, Runtime};
+use futures::SinkExt;
+use log::{error, info};
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{NoTls, Row};
+
+use super::source::DataSource;
+use super::update::{with_retry, LoadedFile, UpdateError, UpdateStatus};
+use super::{
+    Airport, AirportType, BoundingBox, Frequency, FrequencyType, LocationPoint, LocationType,
+    NavDataStore, Navaid, NavaidType, Runway, SearchFilter, METERS_PER_NM,
+};
+use crate::messages::ERROR_UPDATE_STATUS_ACCESS;
+
+#[derive(Clone, Copy)]
+enum ColumnType {
+    Text,
+    Integer,
+    Float,
+}
+
+impl ColumnType {
+    fn sql(&self) -> &'static str {
+        match self {
+            ColumnType::Text => "TEXT",
+            ColumnType::Integer => "BIGINT",
+            ColumnType::Float => "DOUBLE PRECISION",
+        }
+    }
+
+    /// Converts a CSV value to this type. Values that do not fit become NULL
+    fn sanitize<'a>(&self, value: &'a str) -> std::borrow::Cow<'a, str> {
+        let value = value.trim();
+        match self {
+            ColumnType::Text => value.into(),
+            ColumnType::Integer => match (value.parse::<i64>(), value.parse::<f64>()) {
+                (Ok(_), _) => value.into(),
+                (_, Ok(float)) if float.is_finite() => (float as i64).to_string().into(),
+                _ => "".into(),
+            },
+            ColumnType::Float => match value.parse::<f64>() {
+                Ok(float) if float.is_finite() => value.into(),
+                _ => "".into(),
+            },
+        }
+    }
+}
+
+/// Table loaded from an OurAirports CSV file. CSV columns are inserted in order.
+/// Same schema as the SQLite tables, plus a geography column for located rows
+struct PgTable {
+    name: &'static str,
+    /// Name used in logs
+    label: &'static str,
+    file: &'static str,
+    primary_key: &'static str,
+    columns: &'static [(&'static str, ColumnType)],
+    /// Indexed columns
+    indexes: &'static [&'static str],
+    /// Latitude and longitude columns the location is computed from
+    location: Option<(&'static str, &'static str)>,
+}
+
+impl PgTable {
+    fn schema(&self) -> String {
+        let mut columns = self
+            .columns
+            .iter()
+            .map(|(name, column_type)| format!("{} {}", name, column_type.sql()))
+            .collect::<Vec<String>>();
+        if self.location.is_some() {
+            columns.push("location GEOGRAPHY".to_owned());
+        }
+        columns.push(format!("PRIMARY KEY ({})", self.primary_key));
+        format!("({})", columns.join(", "))
+    }
+
+    fn column_names(&self) -> String {
+        self.columns
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+
+    /// Rewrites the CSV data with values matching the column types, ready for COPY
+    fn sanitize(&self, data: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut reader = csv::ReaderBuilder::new().from_reader(data.as_bytes());
+        let mut writer = csv::Writer::from_writer(vec![]);
+        for result in reader.records() {
+            let record = result?;
+            if record.len() < self.columns.len() {
+                return Err(crate::messages::CSV_FORMAT_ERROR.into());
+            }
+            writer.write_record(
+                self.columns
+                    .iter()
+                    .zip(record.iter())
+                    .map(|((_, column_type), value)| column_type.sanitize(value).into_owned()),
+            )?;
+        }
+        Ok(writer.into_inner()?)
+    }
+}
+
+const AIRPORTS_TABLE: PgTable = PgTable {
+    name: "airports",
+    label: "airports",
+    file: super::AIRPORT_CSV,
+    primary_key: "icao_code",
+    columns: &[
+        ("id", ColumnType::Integer),
+        ("icao_code", ColumnType::Text),
+        ("type", ColumnType::Text),
+        ("name", ColumnType::Text),
+        ("latitude_deg", ColumnType::Float),
+        ("longitude_deg", ColumnType::Float),
+        ("elevation_ft", ColumnType::Integer),
+        ("continent", ColumnType::Text),
+        ("iso_country", ColumnType::Text),
+        ("iso_region", ColumnType::Text),
+        ("municipality", ColumnType::Text),
+        ("scheduled_service", ColumnType::Text),
+        ("gps_code", ColumnType::Text),
+        ("iata_code", ColumnType::Text),
+        ("local_code", ColumnType::Text),
+        ("home_link", ColumnType::Text),
+        ("wikipedia_link", ColumnType::Text),
+        ("keywords", ColumnType::Text),
+    ],
+    indexes: &["name", "municipality", "iata_code", "iso_country", "type"],
+    location: Some(("latitude_deg", "longitude_deg")),
+};
+
+const AIRPORT_FREQUENCIES_TABLE: PgTable = PgTable {
+    name: "airport_frequencies",
+    label: "airport frequencies",
+    file: super::AIRPORT_FREQUENCY_CSV,
+    primary_key: "id",
+    columns: &[
+        ("id", ColumnType::Integer),
+        ("airport_ref", ColumnType::Integer),
+        ("airport_icao_code", ColumnType::Text),
+        ("type", ColumnType::Text),
+        ("description", ColumnType::Text),
+        ("frequency_mhz", ColumnType::Float),
+    ],
+    indexes: &["airport_icao_code"],
+    location: None,
+};
+
+const AIRPORT_RUNWAYS_TABLE: PgTable = PgTable {
+    name: "airport_runways",
+    label: "airport runways",
+    file: super::AIRPORT_RUNWAY_CSV,
+    primary_key: "id",
+    columns: &[
+        ("id", ColumnType::Integer),
+        ("airport_ref", ColumnType::Integer),
+        ("airport_icao_code", ColumnType::Text),
+        ("length_ft", ColumnType::Integer),
+        ("width_ft", ColumnType::Integer),
+        ("surface", ColumnType::Integer),
+        ("lighted", ColumnType::Integer),
+        ("closed", ColumnType::Integer),
+        ("le_ident", ColumnType::Text),
+        ("le_latitude_deg", ColumnType::Float),
+        ("le_longitude_deg", ColumnType::Float),
+        ("le_elevation_ft", ColumnType::Integer),
+        ("le_heading_degt", ColumnType::Integer),
+        ("le_displaced_threshold_ft", ColumnType::Integer),
+        ("he_ident", ColumnType::Text),
+        ("he_latitude_deg", ColumnType::Float),
+        ("he_longitude_deg", ColumnType::Float),
+        ("he_elevation_ft", ColumnType::Integer),
+        ("he_heading_degt", ColumnType::Integer),
+        ("he_displaced_threshold_ft", ColumnType::Integer),
+    ],
+    indexes: &["airport_icao_code"],
+    location: None,
+};
+
+const NAVAIDS_TABLE: PgTable = PgTable {
+    name: "navaids",
+    label: "navaids",
+    file: super::NAVAID_CSV,
+    primary_key: "id",
+    columns: &[
+        ("id", ColumnType::Integer),
+        ("filename", ColumnType::Text),
+        ("icao_code", ColumnType::Text),
+        ("name", ColumnType::Text),
+        ("type", ColumnType::Text),
+        ("frequency_khz", ColumnType::Integer),
+        ("latitude_deg", ColumnType::Float),
+        ("longitude_deg", ColumnType::Float),
+        ("elevation_ft", ColumnType::Integer),
+        ("iso_country", ColumnType::Text),
+        ("dme_frequency_khz", ColumnType::Integer),
+        ("dme_channel", ColumnType::Text),
+        ("dme_latitude_deg", ColumnType::Float),
+        ("dme_longitude_deg", ColumnType::Float),
+        ("dme_elevation_ft", ColumnType::Integer),
+        ("slaved_variation_deg", ColumnType::Integer),
+        ("magnetic_variation_deg", ColumnType::Integer),
+        ("usagetype", ColumnType::Text),
+        ("power", ColumnType::Text),
+        ("associated_airport", ColumnType::Text),
+    ],
+    indexes: &[
+        "icao_code",
+        "name",
+        "filename",
+        "associated_airport",
+        "type",
+        "iso_country",
+    ],
+    location: Some(("latitude_deg", "longitude_deg")),
+};
+
+const PG_TABLES: [&PgTable; 4] = [
+    &AIRPORTS_TABLE,
+    &AIRPORT_FREQUENCIES_TABLE,
+    &AIRPORT_RUNWAYS_TABLE,
+    &NAVAIDS_TABLE,
+];
+
+pub struct PostgresBackend {
+    pool: Pool,
+    status: Mutex<UpdateStatus>,
+}
+
+impl PostgresBackend {
+    pub async fn new(database_url: &str) -> PostgresBackend {
+        let mut config = Config::new();
+        config.url = Some(database_url.to_string());
+        let pool = config
+            .create_pool(Some(Runtime::Tokio1), NoTls)
+            .expect("Invalid PostgreSQL URL");
+
+        let backend = PostgresBackend {
+            pool,
+            status: Mutex::new(UpdateStatus::default()),
+        };
+        backend
+            .create_tables()
+            .await
+            .expect("Could not create PostgreSQL tables");
+        backend
+    }
+
+    async fn create_tables(&self) -> Result<(), Box<dyn Error>> {
+        info!("Start database creation");
+        let client = self.pool.get().await?;
+        client
+            .batch_execute(
+                "SET client_min_messages = WARNING;
+                CREATE EXTENSION IF NOT EXISTS postgis;
+                CREATE TABLE IF NOT EXISTS data_last_update (
+                    file TEXT PRIMARY KEY NOT NULL,
+                    sha TEXT NOT NULL,
+                    date BIGINT
+                );",
+            )
+            .await?;
+        for table in PG_TABLES {
+            client
+                .batch_execute(&format!(
+                    "CREATE TABLE IF NOT EXISTS {} {};",
+                    table.name,
+                    table.schema()
+                ))
+                .await?;
+        }
+        info!("Database fully created");
+        Ok(())
+    }
+
+    /// Returns true if the sha differs from the one of the last successful load of the file
+    async fn is_sha_updated(&self, file: &str, sha: &str) -> Result<bool, Box<dyn Error>> {
+        let client = self.pool.get().await?;
+        let row = client
+            .query_opt(
+                "SELECT 1 FROM data_last_update WHERE file = $1 AND sha = $2",
+                &[&file, &sha],
+            )
+            .await?;
+        Ok(row.is_none())
+    }
+
+    /// Loads CSV data into a shadow table and swaps it with the live table, together
+    /// with the file sha, in a single transaction. Readers keep using the previous
+    /// data until the commit, and on failure, nothing changes
+    async fn load_table(
+        &self,
+        table: &PgTable,
+        data: &str,
+        sha: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let data = table.sanitize(data)?;
+        let shadow = format!("{}_new", table.name);
+
+        let mut client = self.pool.get().await?;
+        let transaction = client.transaction().await?;
+        transaction
+            .batch_execute(&format!(
+                "SET LOCAL client_min_messages = WARNING;
+                DROP TABLE IF EXISTS {}; CREATE TABLE {} {};",
+                shadow,
+                shadow,
+                table.schema()
+            ))
+            .await?;
+
+        let sink = transaction
+            .copy_in(&format!(
+                "COPY {} ({}) FROM STDIN WITH (FORMAT csv)",
+                shadow,
+                table.column_names()
+            ))
+            .await?;
+        futures::pin_mut!(sink);
+        sink.send(Bytes::from(data)).await?;
+        let count = sink.finish().await?;
+
+        let mut statements = vec![];
+        if let Some((latitude, longitude)) = table.location {
+            statements.push(format!(
+                "UPDATE {} SET location = ST_SetSRID(ST_MakePoint({}, {}), 4326)::geography;",
+                shadow, longitude, latitude
+            ));
+            statements.push(format!("CREATE INDEX ON {} USING GIST (location);", shadow));
+        }
+        for column in table.indexes {
+            statements.push(format!("CREATE INDEX ON {} ({});", shadow, column));
+        }
+        statements.push(format!(
+            "DROP TABLE {}; ALTER TABLE {} RENAME TO {};",
+            table.name, shadow, table.name
+        ));
+        transaction.batch_execute(&statements.join("\n")).await?;
+        transaction
+            .execute(
+                "INSERT INTO data_last_update (file, sha, date)
+                VALUES ($1, $2, extract(epoch from now())::bigint)
+                ON CONFLICT (file) DO UPDATE SET sha = excluded.sha, date = excluded.date",
+                &[&table.file, &sha],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        info!("{} {} loaded", count, table.label);
+        Ok(())
+    }
+
+    /// Reloads a table if its file changed. Returns the sha of the loaded data
+    async fn update_table(
+        &self,
+        source: &DataSource,
+        table: &PgTable,
+        sha: Option<&String>,
+    ) -> Result<Option<String>, UpdateError> {
+        let load_error = |err: Box<dyn Error>| UpdateError::Load {
+            file: table.file.to_string(),
+            reason: err.to_string(),
+        };
+
+        let sha = sha.ok_or_else(|| UpdateError::MissingFile(table.file.to_string()))?;
+        if !self
+            .is_sha_updated(table.file, sha)
+            .await
+            .map_err(load_error)?
+        {
+            info!("{} is up to date", table.file);
+            return Ok(Some(sha.clone()));
+        }
+
+        let data = with_retry(&format!("Downloading {}", table.file), || {
+            source.fetch(table.file)
+        })
+        .await
+        .map_err(|reason| UpdateError::Fetch {
+            file: table.file.to_string(),
+            reason,
+        })?;
+        self.load_table(table, &data, sha)
+            .await
+            .map_err(load_error)?;
+        Ok(Some(sha.clone()))
+    }
+
+    /// Runways, frequencies and navaids of the given airports
+    async fn add_airport_details(&self, airports: &mut [Airport]) -> Result<(), Box<dyn Error>> {
+        let codes = airports
+            .iter()
+            .map(|airport| airport.icao_code.clone())
+            .collect::<Vec<String>>();
+        let client = self.pool.get().await?;
+        let runways = client
+            .query(
+                "SELECT * FROM airport_runways WHERE airport_icao_code = ANY($1) ORDER BY id",
+                &[&codes],
+            )
+            .await?;
+        let frequencies = client
+            .query(
+                "SELECT * FROM airport_frequencies WHERE airport_icao_code = ANY($1) ORDER BY id",
+                &[&codes],
+            )
+            .await?;
+        let navaids = client
+            .query(
+                "SELECT * FROM navaids WHERE associated_airport = ANY($1) ORDER BY id",
+                &[&codes],
+            )
+            .await?;
+
+        let mut runways = group_by_airport(&runways, "airport_icao_code", read_runway)?;
+        let mut frequencies = group_by_airport(&frequencies, "airport_icao_code", read_frequency)?;
+        let mut navaids = group_by_airport(&navaids, "associated_airport", read_navaid)?;
+        for airport in airports.iter_mut() {
+            airport.runways = runways.remove(&airport.icao_code).unwrap_or_default();
+            airport.frequencies = frequencies.remove(&airport.icao_code).unwrap_or_default();
+            airport.navaids = navaids.remove(&airport.icao_code).unwrap_or_default();
+        }
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl NavDataStore for PostgresBackend {
+    async fn get_airport_by_icao_code(
+        &self,
+        icao: String,
+    ) -> Result<Option<Airport>, Box<dyn Error>> {
+        let row = {
+            let client = self.pool.get().await?;
+            client
+                .query_opt(
+                    "SELECT * FROM airports WHERE icao_code = $1",
+                    &[&icao.to_uppercase()],
+                )
+                .await?
+        };
+        let mut airports = match row {
+            Some(row) => vec![read_airport(&row)?],
+            None => return Ok(None),
+        };
+        self.add_airport_details(&mut airports).await?;
+        Ok(airports.pop())
+    }
+
+    async fn get_navaids_by_icao_code(&self, icao: String) -> Result<Vec<Navaid>, Box<dyn Error>> {
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(
+                "SELECT * FROM navaids WHERE icao_code = $1 ORDER BY id",
+                &[&icao.to_uppercase()],
+            )
+            .await?;
+        rows.iter().map(read_navaid).collect()
+    }
+
+    async fn search_airport(&self, filter: SearchFilter) -> Result<Vec<Airport>, Box<dyn Error>> {
+        let mut query =
+            SearchQuery::new(&filter, &["icao_code", "name", "municipality", "iata_code"]);
+        if let Some(airport_type) = &filter.r#type {
+            query.add_condition("type = {}", Box::new(airport_type.to_lowercase()));
+        }
+        let rows = {
+            let client = self.pool.get().await?;
+            client
+                .query(&query.sql("airports", &filter), &query.params())
+                .await?
+        };
+        let mut airports = rows
+            .iter()
+            .map(|row| {
+                let mut airport = read_airport(row)?;
+                airport.distance_nm = row.try_get("distance_nm")?;
+                Ok(airport)
+            })
+            .collect::<Result<Vec<Airport>, Box<dyn Error>>>()?;
+        if !filter.skip_details {
+            self.add_airport_details(&mut airports).await?;
+        }
+        Ok(airports)
+    }
+
+    async fn search_navaid(&self, filter: SearchFilter) -> Result<Vec<Navaid>, Box<dyn Error>> {
+        let mut query = SearchQuery::new(&filter, &["icao_code", "name", "associated_airport"]);
+        if let Some(navaid_type) = &filter.r#type {
+            query.add_condition("type = {}", Box::new(navaid_type.to_uppercase()));
+        }
+        let client = self.pool.get().await?;
+        let rows = client
+            .query(&query.sql("navaids", &filter), &query.params())
+            .await?;
+        rows.iter()
+            .map(|row| {
+                let mut navaid = read_navaid(row)?;
+                navaid.distance_nm = row.try_get("distance_nm")?;
+                Ok(navaid)
+            })
+            .collect()
+    }
+
+    /// Reloads the files whose sha changed. A failing file does not prevent the
+    /// others from being updated
+    async fn load(&self, source: &DataSource) -> Result<(), UpdateError> {
+        let shas = with_retry("Listing data files", || source.get_list_of_sha())
+            .await
+            .map_err(UpdateError::Source)?;
+
+        let mut result = Ok(());
+        for table in PG_TABLES {
+            let file_result = self.update_table(source, table, shas.get(table.file)).await;
+            if let Err(err) = &file_result {
+                error!("{}", err);
+                result = Err(err.clone());
+            }
+            self.status
+                .lock()
+                .expect(ERROR_UPDATE_STATUS_ACCESS)
+                .record_file(table.file, &file_result);
+        }
+        result
+    }
+
+    /// Data files loaded in the database, with their row count
+    async fn loaded_files(&self) -> Result<Vec<LoadedFile>, Box<dyn Error>> {
+        let client = self.pool.get().await?;
+        let mut loaded = vec![];
+        for table in PG_TABLES {
+            let query = format!(
+                "SELECT sha, date, (SELECT count(*) FROM {}) AS rows FROM data_last_update WHERE file = $1",
+                table.name
+            );
+            if let Some(row) = client.query_opt(&query, &[&table.file]).await? {
+                loaded.push(LoadedFile {
+                    file: table.file.to_string(),
+                    sha: row.try_get("sha")?,
+                    date: row.try_get::<_, Option<i64>>("date")?.unwrap_or_default(),
+                    rows: row.try_get("rows")?,
+                });
+            }
+        }
+        Ok(loaded)
+    }
+
+    fn update_status(&self) -> &Mutex<UpdateStatus> {
+        &self.status
+    }
+}
+
+/// Search query on a table with latitude_deg, longitude_deg and location columns
+struct SearchQuery {
+    conditions: Vec<String>,
+    params: Vec<Box<dyn ToSql + Sync>>,
+    /// Placeholder of the searched point, for proximity searchs
+    center: Option<String>,
+}
+
+impl SearchQuery {
+    /// Query with the criteria shared by all searchs. Text search is done on the
+    /// given columns
+    fn new(filter: &SearchFilter, search_columns: &[&str]) -> SearchQuery {
+        let mut query = SearchQuery {
+            conditions: vec![],
+            params: vec![],
+            center: None,
+        };
+        if let Some(country) = &filter.country {
+            query.add_condition("iso_country = {}", Box::new(country.to_uppercase()));
+        }
+        if let Some(search) = &filter.search {
+            let search = query.add_param(Box::new(search.clone()));
+            let likes = search_columns
+                .iter()
+                .map(|column| format!("{} ILIKE '%' || {} || '%'", column, search))
+                .collect::<Vec<String>>();
+            query.conditions.push(format!("({})", likes.join(" OR ")));
+        }
+        if let Some((latitude, longitude)) = filter.center() {
+            let longitude = query.add_param(Box::new(longitude));
+            let latitude = query.add_param(Box::new(latitude));
+            let center = format!(
+                "ST_SetSRID(ST_MakePoint({}, {}), 4326)::geography",
+                longitude, latitude
+            );
+            let radius = query.add_param(Box::new(filter.radius_nm() * METERS_PER_NM));
+            query.conditions.push(format!(
+                "ST_DWithin(location, {}, {}, false)",
+                center, radius
+            ));
+            query.center = Some(center);
+        }
+        if let Some(bbox) = &filter.bbox {
+            query.add_bbox(bbox);
+        }
+        query
+    }
+
+    /// Adds a parameter and returns its placeholder
+    fn add_param(&mut self, param: Box<dyn ToSql + Sync>) -> String {
+        self.params.push(param);
+        format!("${}", self.params.len())
+    }
+
+    /// Adds a condition with a single parameter, written {} in the condition
+    fn add_condition(&mut self, condition: &str, param: Box<dyn ToSql + Sync>) {
+        let placeholder = self.add_param(param);
+        self.conditions
+            .push(condition.replace("{}", placeholder.as_str()));
+    }
+
+    fn add_bbox(&mut self, bbox: &BoundingBox) {
+        let min_lat = self.add_param(Box::new(bbox.min_lat));
+        let max_lat = self.add_param(Box::new(bbox.max_lat));
+        let min_lon = self.add_param(Box::new(bbox.min_lon));
+        let max_lon = self.add_param(Box::new(bbox.max_lon));
+        let longitude_condition = match bbox.crosses_antimeridian() {
+            true => format!(
+                "(longitude_deg >= {} OR longitude_deg <= {})",
+                min_lon, max_lon
+            ),
+            false => format!("longitude_deg BETWEEN {} AND {}", min_lon, max_lon),
+        };
+        self.conditions.push(format!(
+            "latitude_deg BETWEEN {} AND {} AND {}",
+            min_lat, max_lat, longitude_condition
+        ));
+    }
+
+    fn sql(&self, table: &str, filter: &SearchFilter) -> String {
+        let (distance, order) = match &self.center {
+            Some(center) => (
+                format!(
+                    "ST_Distance(location, {}, false) / {}",
+                    center, METERS_PER_NM
+                ),
+                "distance_nm, id",
+            ),
+            None => ("NULL::DOUBLE PRECISION".to_owned(), "id"),
+        };
+        let where_clause = match self.conditions.is_empty() {
+            true => String::new(),
+            false => format!(" WHERE {}", self.conditions.join(" AND ")),
+        };
+        format!(
+            "SELECT *, {} AS distance_nm FROM {}{} ORDER BY {} LIMIT {} OFFSET {}",
+            distance,
+            table,
+            where_clause,
+            order,
+            filter.page_size(),
+            filter.offset()
+        )
+    }
+
+    fn params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.params.iter().map(|param| param.as_ref()).collect()
+    }
+}
+
+/// Rows read and grouped by the airport code found in the given column
+fn group_by_airport<T>(
+    rows: &[Row],
+    column: &str,
+    read: fn(&Row) -> Result<T, Box<dyn Error>>,
+) -> Result<HashMap<String, Vec<T>>, Box<dyn Error>> {
+    let mut groups: HashMap<String, Vec<T>> = HashMap::new();
+    for row in rows {
+        groups
+            .entry(text(row, column)?)
+            .or_default()
+            .push(read(row)?);
+    }
+    Ok(groups)
+}
+
+fn text(row: &Row, column: &str) -> Result<String, Box<dyn Error>> {
+    Ok(row
+        .try_get::<_, Option<String>>(column)?
+        .unwrap_or_default())
+}
+
+fn integer(row: &Row, column: &str) -> Result<i64, Box<dyn Error>> {
+    Ok(row.try_get::<_, Option<i64>>(column)?.unwrap_or_default())
+}
+
+fn float(row: &Row, column: &str) -> Result<f64, Box<dyn Error>> {
+    Ok(row.try_get::<_, Option<f64>>(column)?.unwrap_or_default())
+}
+
+fn location(row: &Row, latitude: &str, longitude: &str) -> Result<LocationPoint, Box<dyn Error>> {
+    Ok(LocationPoint {
+        r#type: LocationType::Point,
+        coordinates: vec![float(row, longitude)?, float(row, latitude)?],
+    })
+}
+
+fn read_airport(row: &Row) -> Result<Airport, Box<dyn Error>> {
+    Ok(Airport {
+        id: integer(row, "id")?,
+        icao_code: text(row, "icao_code")?,
+        r#type: AirportType::from_str(text(row, "type")?.as_str()).unwrap_or_default(),
+        name: text(row, "name")?,
+        location: location(row, "latitude_deg", "longitude_deg")?,
+        elevation_ft: integer(row, "elevation_ft")?,
+        continent: text(row, "continent")?,
+        iso_country: text(row, "iso_country")?,
+        iso_region: text(row, "iso_region")?,
+        municipality: text(row, "municipality")?,
+        scheduled_service: text(row, "scheduled_service")?,
+        gps_code: text(row, "gps_code")?,
+        iata_code: text(row, "iata_code")?,
+        local_code: text(row, "local_code")?,
+        home_link: text(row, "home_link")?,
+        wikipedia_link: text(row, "wikipedia_link")?,
+        keywords: text(row, "keywords")?,
+        ..Default::default()
+    })
+}
+
+fn read_runway(row: &Row) -> Result<Runway, Box<dyn Error>> {
+    Ok(Runway {
+        id: integer(row, "id")?,
+        airport_id: integer(row, "airport_ref")?,
+        airport_icao_code: text(row, "airport_icao_code")?,
+        length_ft: integer(row, "length_ft")?,
+        width_ft: integer(row, "width_ft")?,
+        surface: integer(row, "surface")?,
+        lighted: integer(row, "lighted")?,
+        closed: integer(row, "closed")?,
+        le_ident: text(row, "le_ident")?,
+        le_location: location(row, "le_latitude_deg", "le_longitude_deg")?,
+        le_elevation_ft: integer(row, "le_elevation_ft")?,
+        le_heading_deg_t: integer(row, "le_heading_degt")?,
+        le_displaced_threshold_ft: integer(row, "le_displaced_threshold_ft")?,
+        he_ident: text(row, "he_ident")?,
+        he_location: location(row, "he_latitude_deg", "he_longitude_deg")?,
+        he_elevation_ft: integer(row, "he_elevation_ft")?,
+        he_heading_deg_t: integer(row, "he_heading_degt")?,
+        he_displaced_threshold_ft: integer(row, "he_displaced_threshold_ft")?,
+    })
+}
+
+fn read_frequency(row: &Row) -> Result<Frequency, Box<dyn Error>> {
+    let raw_type = text(row, "type")?;
+    Ok(Frequency {
+        id: integer(row, "id")?,
+        airport_id: integer(row, "airport_ref")?,
+        airport_icao_code: text(row, "airport_icao_code")?,
+        r#type: FrequencyType::from_str(raw_type.as_str()).unwrap_or_default(),
+        raw_type,
+        description: text(row, "description")?,
+        frequency_mhz: float(row, "frequency_mhz")?,
+    })
+}
+
+fn read_navaid(row: &Row) -> Result<Navaid, Box<dyn Error>> {
+    Ok(Navaid {
+        id: integer(row, "id")?,
+        filename: text(row, "filename")?,
+        icao_code: text(row, "icao_code")?,
+        name: text(row, "name")?,
+        r#type: NavaidType::from_str(text(row, "type")?.as_str()).unwrap_or_default(),
+        frequency_khz: integer(row, "frequency_khz")?,
+        location: location(row, "latitude_deg", "longitude_deg")?,
+        elevation_ft: integer(row, "elevation_ft")?,
+        iso_country: text(row, "iso_country")?,
+        dme_frequency_khz: integer(row, "dme_frequency_khz")?,
+        dme_channel: text(row, "dme_channel")?,
+        dme_location: location(row, "dme_latitude_deg", "dme_longitude_deg")?,
+        dme_elevation_ft: integer(row, "dme_elevation_ft")?,
+        slaved_variation_deg: integer(row, "slaved_variation_deg")?,
+        magnetic_variation_deg: integer(row, "magnetic_variation_deg")?,
+        usage_type: text(row, "usagetype")?,
+        power: text(row, "power")?,
+        associated_airport: text(row, "associated_airport")?,
+        distance_nm: None,
+    })
+}
@@ -0,0 +1,221 @@
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Deserialize;
+use std::str::FromStr;
+use tokio::time::Duration;
+
+use crate::config::RefreshConfig;
+
+/// Delay before a new update attempt when the previous one failed
+const FAILED_UPDATE_DELAY: Duration = Duration::from_secs(3600);
+
+/// Length of an AIRAC cycle
+const AIRAC_CYCLE_DAYS: i64 = 28;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum RefreshMode {
+    /// Refresh at a fixed interval
+    #[default]
+    INTERVAL,
+    /// Refresh following a cron expression
+    CRON,
+    /// Refresh on each AIRAC effective date, at 0901Z
+    AIRAC,
+    /// Never refresh automatically
+    NEVER,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+pub enum StartupMode {
+    /// Wait for the data to be loaded before accepting requests
+    LOAD_BEFORE_SERVE,
+    /// Serve the data already in the database while loading
+    #[default]
+    SERVE_STALE,
+}
+
+/// When the data is automatically refreshed
+pub enum Schedule {
+    Interval(Duration),
+    Cron(Box<cron::Schedule>),
+    Airac,
+    Never,
+}
+
+impl TryFrom<&RefreshConfig> for Schedule {
+    type Error = String;
+
+    fn try_from(config: &RefreshConfig) -> Result<Self, Self::Error> {
+        match config.mode {
+            RefreshMode::INTERVAL if config.interval == 0 => {
+                Err("Refresh interval must be greater than 0".to_string())
+            }
+            RefreshMode::INTERVAL => Ok(Schedule::Interval(Duration::from_secs(config.interval))),
+            RefreshMode::CRON => {
+                let expression = config
+                    .cron
+                    .as_ref()
+                    .ok_or("A cron expression is required in CRON refresh mode")?;
+                let schedule = cron::Schedule::from_str(expression)
+                    .map_err(|err| format!("Invalid cron expression {} : {}", expression, err))?;
+                Ok(Schedule::Cron(Box::new(schedule)))
+            }
+            RefreshMode::AIRAC => Ok(Schedule::Airac),
+            RefreshMode::NEVER => Ok(Schedule::Never),
+        }
+    }
+}
+
+impl Schedule {
+    /// Delay before the next refresh, if any. Failed refreshes are retried sooner,
+    /// unless automatic refreshes are disabled
+    pub fn next_delay(&self, last_failed: bool) -> Option<Duration> {
+        self.next_delay_after(Utc::now(), last_failed)
+    }
+
+    fn next_delay_after(&self, now: DateTime<Utc>, last_failed: bool) -> Option<Duration> {
+        let next = match self {
+            Schedule::Interval(interval) => Some(*interval),
+            Schedule::Cron(schedule) => schedule
+                .after(&now)
+                .next()
+                .map(|date| delay_until(now, date)),
+            Schedule::Airac => Some(delay_until(now, next_airac_date(now))),
+            Schedule::Never => None,
+        }?;
+        match last_failed {
+            true => Some(next.min(FAILED_UPDATE_DELAY)),
+            false => Some(next),
+        }
+    }
+}
+
+fn delay_until(now: DateTime<Utc>, date: DateTime<Utc>) -> Duration {
+    (date - now).to_std().unwrap_or_default()
+}
+
+/// First AIRAC effective date after the given date. Cycles are 28 days long,
+/// counted from AIRAC 2401, effective on 25 January 2024
+fn next_airac_date(after: DateTime<Utc>) -> DateTime<Utc> {
+    let reference = Utc.with_ymd_and_hms(2024, 1, 25, 9, 1, 0).unwrap();
+    let cycle = chrono::Duration::days(AIRAC_CYCLE_DAYS);
+    let elapsed_cycles = (after - reference)
+        .num_seconds()
+        .div_euclid(cycle.num_seconds());
+    reference + cycle * (elapsed_cycles as i32 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+    const DAY: Duration = Duration::from_secs(24 * 3600);
+
+    fn date(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, min, sec)
+            .unwrap()
+    }
+
+    fn schedule(yaml: &str) -> Schedule {
+        Schedule::try_from(&serde_yaml::from_str::<RefreshConfig>(yaml).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn airac_dates_follow_the_published_cycles() {
+        // AIRAC 2402, 2501 and 2601
+        assert_eq!(
+            next_airac_date(date(2024, 2, 1, 0, 0, 0)),
+            date(2024, 2, 22, 9, 1, 0)
+        );
+        assert_eq!(
+            next_airac_date(date(2025, 1, 1, 0, 0, 0)),
+            date(2025, 1, 23, 9, 1, 0)
+        );
+        assert_eq!(
+            next_airac_date(date(2026, 1, 1, 0, 0, 0)),
+            date(2026, 1, 22, 9, 1, 0)
+        );
+        // Far from the reference cycle, on both sides
+        assert_eq!(
+            next_airac_date(date(2034, 1, 20, 0, 0, 0)),
+            date(2034, 2, 9, 9, 1, 0)
+        );
+        assert_eq!(
+            next_airac_date(date(2023, 12, 1, 0, 0, 0)),
+            date(2023, 12, 28, 9, 1, 0)
+        );
+    }
+
+    #[test]
+    fn airac_effective_dates_schedule_the_next_cycle() {
+        assert_eq!(
+            next_airac_date(date(2025, 1, 23, 9, 0, 59)),
+            date(2025, 1, 23, 9, 1, 0)
+        );
+        assert_eq!(
+            next_airac_date(date(2025, 1, 23, 9, 1, 0)),
+            date(2025, 2, 20, 9, 1, 0)
+        );
+        assert_eq!(
+            next_airac_date(date(2024, 1, 25, 9, 1, 0)),
+            date(2024, 2, 22, 9, 1, 0)
+        );
+        assert_eq!(
+            Schedule::Airac.next_delay_after(date(2025, 1, 23, 9, 0, 59), false),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            Schedule::Airac.next_delay_after(date(2025, 1, 23, 9, 1, 0), false),
+            Some(DAY * 28)
+        );
+    }
+
+    #[test]
+    fn cron_schedules_wait_for_the_next_match() {
+        let nightly = schedule("mode: CRON\ncron: \"0 0 3 * * *\"\n");
+        assert_eq!(
+            nightly.next_delay_after(date(2024, 3, 10, 2, 59, 59), false),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            nightly.next_delay_after(date(2024, 3, 10, 3, 0, 0), false),
+            Some(DAY)
+        );
+        assert_eq!(
+            nightly.next_delay_after(date(2024, 12, 31, 4, 0, 0), false),
+            Some(DAY - HOUR)
+        );
+    }
+
+    #[test]
+    fn failed_refreshes_are_retried_within_an_hour() {
+        let now = date(2024, 3, 10, 12, 0, 0);
+        let daily = Schedule::Interval(DAY);
+        assert_eq!(daily.next_delay_after(now, false), Some(DAY));
+        assert_eq!(daily.next_delay_after(now, true), Some(FAILED_UPDATE_DELAY));
+        let frequent = Schedule::Interval(Duration::from_secs(600));
+        assert_eq!(
+            frequent.next_delay_after(now, true),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(
+            Schedule::Airac.next_delay_after(now, true),
+            Some(FAILED_UPDATE_DELAY)
+        );
+        assert_eq!(Schedule::Never.next_delay_after(now, true), None);
+    }
+
+    #[test]
+    fn invalid_schedules_are_rejected() {
+        for yaml in [
+            "mode: INTERVAL\ninterval: 0\n",
+            "mode: CRON\n",
+            "mode: CRON\ncron: every night\n",
+        ] {
+            let config = serde_yaml::from_str::<RefreshConfig>(yaml).unwrap();
+            assert!(Schedule::try_from(&config).is_err(), "{}", yaml);
+        }
+    }
+}
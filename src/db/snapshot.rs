@@ -0,0 +1,195 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use super::source::DataSource;
+use super::update::{now, with_retry};
+use super::CSV_FILES;
+
+/// Version of the snapshot format. Bumped on incompatible changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+/// Extension identifying snapshot files among the local data sources
+pub const SNAPSHOT_EXTENSION: &str = ".navdata.zst";
+const SNAPSHOT_COMPRESSION_LEVEL: i32 = 12;
+
+/// Content of a data file, with the sha it had in its source
+#[derive(Serialize, Deserialize)]
+pub struct SnapshotFile {
+    pub file: String,
+    pub sha: String,
+    pub data: String,
+}
+
+/// A complete dataset : the airports, frequencies, runways and navaids CSV files,
+/// with the shas of the source they were read from. Stored as zstd compressed JSON.
+/// Since the source shas are kept, a backend loaded from a snapshot only downloads
+/// the files that changed since on its next refresh
+/// The files are kept as CSV text: loading a snapshot saves the download, not
+/// the parsing and insertion of the rows
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    /// Unix timestamp of the export
+    pub created: i64,
+    pub files: Vec<SnapshotFile>,
+}
+
+/// Files are shown with their shas only, their content is too large to be logged
+impl fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("version", &self.version)
+            .field("created", &self.created)
+            .field("files", &self.shas())
+            .finish()
+    }
+}
+
+impl Snapshot {
+    /// Reads all the data files of a source
+    pub async fn from_source(source: &DataSource) -> Result<Snapshot, Box<dyn Error>> {
+        let shas = with_retry("Listing data files", || source.get_list_of_sha()).await?;
+        let mut files = vec![];
+        for file in CSV_FILES {
+            let sha = shas
+                .get(file)
+                .ok_or_else(|| format!("{} not found in data source", file))?;
+            let data = with_retry(&format!("Downloading {}", file), || source.fetch(file)).await?;
+            files.push(SnapshotFile {
+                file: file.to_string(),
+                sha: sha.clone(),
+                data,
+            });
+        }
+        Ok(Snapshot {
+            version: SNAPSHOT_VERSION,
+            created: now(),
+            files,
+        })
+    }
+
+    pub fn read(path: &Path) -> Result<Snapshot, Box<dyn Error>> {
+        info!("Reading snapshot {}", path.display());
+        let decoder = zstd::stream::read::Decoder::new(BufReader::new(File::open(path)?))?;
+        let snapshot: Snapshot = serde_json::from_reader(decoder)
+            .map_err(|err| format!("{} is not a valid snapshot : {}", path.display(), err))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(format!(
+                "{} has snapshot format version {}, expected {}",
+                path.display(),
+                snapshot.version,
+                SNAPSHOT_VERSION
+            )
+            .into());
+        }
+        Ok(snapshot)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut encoder = zstd::stream::write::Encoder::new(
+            BufWriter::new(File::create(path)?),
+            SNAPSHOT_COMPRESSION_LEVEL,
+        )?;
+        serde_json::to_writer(&mut encoder, self)?;
+        encoder.finish()?.flush()?;
+        info!("Snapshot written to {}", path.display());
+        Ok(())
+    }
+
+    pub fn shas(&self) -> HashMap<String, String> {
+        self.files
+            .iter()
+            .map(|file| (file.file.clone(), file.sha.clone()))
+            .collect()
+    }
+
+    /// Content of a data file
+    pub fn data(&self, file: &str) -> Option<&str> {
+        self.files
+            .iter()
+            .find(|snapshot_file| snapshot_file.file == file)
+            .map(|snapshot_file| snapshot_file.data.as_str())
+    }
+
+    /// Takes the content of a data file out of the snapshot
+    pub fn take(self, file: &str) -> Option<String> {
+        self.files
+            .into_iter()
+            .find(|snapshot_file| snapshot_file.file == file)
+            .map(|snapshot_file| snapshot_file.data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use uuid::Uuid;
+
+    fn snapshot_path() -> PathBuf {
+        std::env::temp_dir().join(format!("navdata-{}{}", Uuid::new_v4(), SNAPSHOT_EXTENSION))
+    }
+
+    fn snapshot(version: u32) -> Snapshot {
+        Snapshot {
+            version,
+            created: 1700000000,
+            files: CSV_FILES
+                .iter()
+                .map(|file| SnapshotFile {
+                    file: file.to_string(),
+                    sha: format!("sha of {}", file),
+                    data: format!("\"id\",\"ident\"\n1,\"{}\"\n", file),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn snapshots_are_read_as_written() {
+        let path = snapshot_path();
+        snapshot(SNAPSHOT_VERSION).write(&path).unwrap();
+        let read = Snapshot::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let written = snapshot(SNAPSHOT_VERSION);
+        assert_eq!(read.created, written.created);
+        assert_eq!(read.shas(), written.shas());
+        for file in CSV_FILES {
+            assert_eq!(read.data(file), written.data(file));
+        }
+        assert_eq!(read.data("unknown.csv"), None);
+    }
+
+    #[test]
+    fn snapshots_of_another_format_version_are_rejected() {
+        let path = snapshot_path();
+        snapshot(SNAPSHOT_VERSION + 1).write(&path).unwrap();
+        let err = Snapshot::read(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.to_string().contains(&format!(
+            "has snapshot format version {}, expected {}",
+            SNAPSHOT_VERSION + 1,
+            SNAPSHOT_VERSION
+        )));
+    }
+
+    #[test]
+    fn files_other_than_snapshots_are_rejected() {
+        let path = snapshot_path();
+        std::fs::write(&path, "\"id\",\"ident\"\n").unwrap();
+        assert!(Snapshot::read(&path).is_err());
+        let mut encoder =
+            zstd::stream::write::Encoder::new(File::create(&path).unwrap(), 1).unwrap();
+        encoder.write_all(b"{\"airports\": []}").unwrap();
+        encoder.finish().unwrap();
+        let err = Snapshot::read(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(err.to_string().contains("is not a valid snapshot"));
+    }
+}
@@ -0,0 +1,300 @@
+use crate::config::DataSourceConfig;
+use crate::messages::HTTP_USER_AGENT;
+use log::{debug, info};
+use reqwest::Client;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use super::snapshot::{Snapshot, SnapshotFile, SNAPSHOT_EXTENSION, SNAPSHOT_VERSION};
+use super::update::now;
+use super::CSV_FILES;
+
+pub const DEFAULT_API_URL: &str = "https://api.github.com/repos/davidmegginson/ourairports-data";
+pub const DEFAULT_RAW_URL: &str =
+    "https://raw.githubusercontent.com/davidmegginson/ourairports-data";
+pub const DEFAULT_BRANCH: &str = "main";
+
+const UNEXPECTED_API_ANSWER: &str = "Unexpected answer from repository API";
+
+const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Maximum duration of a request, including the download of the largest CSV file
+const HTTP_TIMEOUT: Duration = Duration::from_secs(300);
+
+static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Client shared by the requests to remote sources, so that a stalled server
+/// cannot hang a refresh
+fn http_client() -> Result<&'static Client, Box<dyn Error>> {
+    if let Some(client) = HTTP_CLIENT.get() {
+        return Ok(client);
+    }
+    let client = Client::builder()
+        .user_agent(HTTP_USER_AGENT)
+        .connect_timeout(HTTP_CONNECT_TIMEOUT)
+        .timeout(HTTP_TIMEOUT)
+        .build()?;
+    Ok(HTTP_CLIENT.get_or_init(|| client))
+}
+
+/// A GitHub hosted ourairports-data repository, or any server exposing the same API
+#[derive(Clone, Debug)]
+pub struct RemoteSource {
+    /// Repository API root, used to get the file shas
+    pub api_url: String,
+    /// Raw files root, used to download the CSV files
+    pub raw_url: String,
+    pub branch: String,
+    /// If set, files are read from this commit instead of the head of the branch
+    pub commit: Option<String>,
+}
+
+impl Default for RemoteSource {
+    fn default() -> Self {
+        RemoteSource {
+            api_url: DEFAULT_API_URL.to_string(),
+            raw_url: DEFAULT_RAW_URL.to_string(),
+            branch: DEFAULT_BRANCH.to_string(),
+            commit: None,
+        }
+    }
+}
+
+impl RemoteSource {
+    /// Git reference the files are read from
+    fn reference(&self) -> &str {
+        self.commit.as_deref().unwrap_or(&self.branch)
+    }
+
+    fn file_url(&self, file: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.raw_url.trim_end_matches('/'),
+            self.reference(),
+            file
+        )
+    }
+
+    async fn get_list_of_sha(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let client = http_client()?;
+        let api_url = self.api_url.trim_end_matches('/');
+
+        let commit_sha = match &self.commit {
+            Some(commit) => commit.clone(),
+            None => {
+                debug!("Looking for branch sha first");
+                let result = client
+                    .get(format!("{}/branches/{}", api_url, self.branch))
+                    .send()
+                    .await?;
+                let data = result.error_for_status()?.json::<Value>().await?;
+                let branch_sha = data
+                    .get("commit")
+                    .and_then(|commit| commit.get("sha"))
+                    .and_then(|sha| sha.as_str())
+                    .ok_or(UNEXPECTED_API_ANSWER)?;
+                debug!("Branch sha is {}", branch_sha);
+                branch_sha.to_string()
+            }
+        };
+
+        let mut shas = HashMap::new();
+        let result = client
+            .get(format!("{}/git/trees/{}", api_url, commit_sha))
+            .send()
+            .await?;
+        let data = result.error_for_status()?.json::<Value>().await?;
+        let tree = data
+            .get("tree")
+            .and_then(|tree| tree.as_array())
+            .ok_or(UNEXPECTED_API_ANSWER)?;
+        for file in tree {
+            let path = file.get("path").and_then(|path| path.as_str());
+            let sha = file.get("sha").and_then(|sha| sha.as_str());
+            if let (Some(path), Some(sha)) = (path, sha) {
+                shas.insert(path.to_string(), sha.to_string());
+            }
+        }
+
+        Ok(shas)
+    }
+}
+
+/// Where the OurAirports CSV files are read from
+#[derive(Clone, Debug)]
+pub enum DataSource {
+    /// A remote ourairports-data repository
+    Remote(RemoteSource),
+    /// A local directory holding the CSV files
+    Directory(PathBuf),
+    /// A local `.tar.zst` or `.zip` archive holding the CSV files
+    Archive(PathBuf),
+    /// A dataset snapshot, with the shas of the source it was exported from
+    Snapshot(PathBuf),
+    /// Files already read in memory, with their shas
+    Decoded(Arc<Snapshot>),
+}
+
+impl Default for DataSource {
+    fn default() -> Self {
+        DataSource::Remote(RemoteSource::default())
+    }
+}
+
+impl From<&DataSourceConfig> for DataSource {
+    fn from(config: &DataSourceConfig) -> Self {
+        match &config.path {
+            Some(path) => DataSource::local(path.clone()),
+            None => DataSource::Remote(RemoteSource {
+                api_url: config.api_url.clone(),
+                raw_url: config.raw_url.clone(),
+                branch: config.branch.clone(),
+                commit: config.commit.clone(),
+            }),
+        }
+    }
+}
+
+impl DataSource {
+    /// Local source from a path, either a directory, an archive or a snapshot
+    pub fn local(path: PathBuf) -> DataSource {
+        if path.is_dir() {
+            DataSource::Directory(path)
+        } else if path.to_string_lossy().ends_with(SNAPSHOT_EXTENSION) {
+            DataSource::Snapshot(path)
+        } else {
+            DataSource::Archive(path)
+        }
+    }
+
+    /// Snapshots and archives are read once here, so that the listing of the files
+    /// and each of their fetches do not decompress them again. Other sources are
+    /// returned as is
+    pub fn decoded(&self) -> Result<Cow<'_, DataSource>, Box<dyn Error>> {
+        let snapshot = match self {
+            DataSource::Snapshot(path) => Snapshot::read(path)?,
+            DataSource::Archive(path) => Snapshot {
+                version: SNAPSHOT_VERSION,
+                created: now(),
+                files: read_archive(path, &CSV_FILES)?
+                    .into_iter()
+                    .map(|(file, data)| SnapshotFile {
+                        file,
+                        sha: sha256(data.as_bytes()),
+                        data,
+                    })
+                    .collect(),
+            },
+            _ => return Ok(Cow::Borrowed(self)),
+        };
+        Ok(Cow::Owned(DataSource::Decoded(Arc::new(snapshot))))
+    }
+
+    /// Returns the content of a CSV file
+    pub async fn fetch(&self, file: &str) -> Result<String, Box<dyn Error>> {
+        match self {
+            DataSource::Remote(remote) => {
+                let result = http_client()?.get(remote.file_url(file)).send().await?;
+                Ok(result.error_for_status()?.text().await?)
+            }
+            DataSource::Directory(path) => Ok(std::fs::read_to_string(path.join(file))?),
+            DataSource::Archive(path) => {
+                let mut files = read_archive(path, &[file])?;
+                files
+                    .remove(file)
+                    .ok_or_else(|| format!("{} not found in {}", file, path.display()).into())
+            }
+            DataSource::Snapshot(path) => Snapshot::read(path)?
+                .take(file)
+                .ok_or_else(|| format!("{} not found in {}", file, path.display()).into()),
+            DataSource::Decoded(snapshot) => snapshot
+                .data(file)
+                .map(str::to_string)
+                .ok_or_else(|| format!("{} not found in data source", file).into()),
+        }
+    }
+
+    /// Returns the sha of each available file. Remote shas are the git blob shas,
+    /// local ones are the SHA-256 of the file contents. Snapshots keep the shas of
+    /// the source they were exported from
+    pub async fn get_list_of_sha(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        match self {
+            DataSource::Remote(remote) => remote.get_list_of_sha().await,
+            DataSource::Directory(path) => {
+                let mut shas = HashMap::new();
+                for file in CSV_FILES {
+                    let data = std::fs::read(path.join(file))?;
+                    shas.insert(file.to_string(), sha256(&data));
+                }
+                Ok(shas)
+            }
+            DataSource::Archive(path) => {
+                let files = read_archive(path, &CSV_FILES)?;
+                Ok(files
+                    .into_iter()
+                    .map(|(file, data)| (file, sha256(data.as_bytes())))
+                    .collect())
+            }
+            DataSource::Snapshot(path) => Ok(Snapshot::read(path)?.shas()),
+            DataSource::Decoded(snapshot) => Ok(snapshot.shas()),
+        }
+    }
+}
+
+fn sha256(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Extracts the requested files from an archive. Files are matched on their name,
+/// whatever folder they are in
+fn read_archive(path: &Path, files: &[&str]) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    info!("Reading {:?} from {}", files, path.display());
+    let name = path.to_string_lossy().to_lowercase();
+    let mut found = HashMap::new();
+
+    let wanted = |entry_path: &Path| {
+        entry_path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .and_then(|file_name| files.iter().find(|file| **file == file_name))
+            .map(|file| file.to_string())
+    };
+
+    if name.ends_with(".zip") {
+        let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            if let Some(file) = entry.enclosed_name().and_then(wanted) {
+                let mut data = String::new();
+                entry.read_to_string(&mut data)?;
+                found.insert(file, data);
+            }
+        }
+    } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+        let decoder = zstd::stream::read::Decoder::new(File::open(path)?)?;
+        let mut archive = tar::Archive::new(decoder);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if let Some(file) = wanted(&entry.path()?) {
+                let mut data = String::new();
+                entry.read_to_string(&mut data)?;
+                found.insert(file, data);
+            }
+        }
+    } else {
+        return Err(format!(
+            "{} is neither a directory, a .tar.zst nor a .zip archive",
+            path.display()
+        )
+        .into());
+    }
+
+    Ok(found)
+}
@@ -0,0 +1,1134 @@
+use crate::messages::{CSV_FORMAT_ERROR, ERROR_SQLITE_ACCESS, ERROR_UPDATE_STATUS_ACCESS};
+use ::sqlite::Connection;
+use async_trait::async_trait;
+use log::{error, info};
+use sqlite::{OpenFlags, State, Statement, Value as SqlValue};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::task::spawn_blocking;
+use uuid::Uuid;
+
+use super::source::DataSource;
+use super::update::{with_retry, LoadedFile, UpdateError, UpdateStatus};
+use super::{
+    Airport, AirportType, BoundingBox, Frequency, FrequencyType, LocationPoint, LocationType,
+    NavDataStore, Navaid, NavaidType, Runway, SearchFilter,
+};
+
+/// Number of rows inserted per transaction when loading a CSV file
+const INSERT_BATCH_SIZE: usize = 5000;
+/// Maximum number of idle read-only connections kept open
+const MAX_IDLE_READERS: usize = 16;
+/// Time a connection waits for a lock held by another one, in milliseconds
+const BUSY_TIMEOUT_MS: usize = 5000;
+/// Path of non persistent databases
+const MEMORY_PATH: &str = ":memory:";
+
+/// Result of SQLite work run in the blocking thread pool
+type SqlResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Table loaded from an OurAirports CSV file. CSV columns are inserted in order
+struct CsvTable {
+    name: &'static str,
+    /// Name used in logs
+    label: &'static str,
+    file: &'static str,
+    schema: &'static str,
+    columns: &'static [&'static str],
+    /// Indexed columns
+    indexes: &'static [&'static str],
+}
+
+impl CsvTable {
+    fn create_indexes(&self) -> String {
+        self.indexes
+            .iter()
+            .map(|column| {
+                format!(
+                    "CREATE INDEX IF NOT EXISTS idx_{}_{} ON {} ({});",
+                    self.name, column, self.name, column
+                )
+            })
+            .collect()
+    }
+}
+
+const AIRPORTS_TABLE: CsvTable = CsvTable {
+    name: "airports",
+    label: "airports",
+    file: super::AIRPORT_CSV,
+    schema: "(
+            id INTEGER UNIQUE,
+            icao_code TEXT UNIQUE PRIMARY KEY NOT NULL,
+            type TEXT,
+            name TEXT,
+            latitude_deg DECIMAL,
+            longitude_deg DECIMAL,
+            elevation_ft INTEGER,
+            continent TEXT,
+            iso_country TEXT,
+            iso_region TEXT,
+            municipality TEXT,
+            scheduled_service TEXT,
+            gps_code TEXT,
+            iata_code TEXT,
+            local_code TEXT,
+            home_link TEXT,
+            wikipedia_link TEXT,
+            keywords TEXT
+        )",
+    columns: &[
+        "id",
+        "icao_code",
+        "type",
+        "name",
+        "latitude_deg",
+        "longitude_deg",
+        "elevation_ft",
+        "continent",
+        "iso_country",
+        "iso_region",
+        "municipality",
+        "scheduled_service",
+        "gps_code",
+        "iata_code",
+        "local_code",
+        "home_link",
+        "wikipedia_link",
+        "keywords",
+    ],
+    indexes: &["name", "municipality", "iata_code", "iso_country", "type"],
+};
+
+const AIRPORT_FREQUENCIES_TABLE: CsvTable = CsvTable {
+    name: "airport_frequencies",
+    label: "airport frequencies",
+    file: super::AIRPORT_FREQUENCY_CSV,
+    schema: "(
+            id INTEGER UNIQUE PRIMARY KEY NOT NULL,
+            airport_ref INTEGER,
+            airport_icao_code TEXT,
+            type TEXT,
+            description TEXT,
+            frequency_mhz DECIMAL
+        )",
+    columns: &[
+        "id",
+        "airport_ref",
+        "airport_icao_code",
+        "type",
+        "description",
+        "frequency_mhz",
+    ],
+    indexes: &["airport_icao_code"],
+};
+
+const AIRPORT_RUNWAYS_TABLE: CsvTable = CsvTable {
+    name: "airport_runways",
+    label: "airport runways",
+    file: super::AIRPORT_RUNWAY_CSV,
+    schema: "(
+            id INTEGER UNIQUE PRIMARY KEY NOT NULL,
+            airport_ref INTEGER,
+            airport_icao_code TEXT,
+            length_ft INTEGER,
+            width_ft INTEGER,
+            surface INTEGER,
+            lighted INTEGER,
+            closed INTEGER,
+            le_ident INTEGER,
+            le_latitude_deg DECIMAL,
+            le_longitude_deg DECIMAL,
+            le_elevation_ft INTEGER,
+            le_heading_degT INTEGER,
+            le_displaced_threshold_ft INTEGER,
+            he_ident INTEGER,
+            he_latitude_deg DECIMAL,
+            he_longitude_deg DECIMAL,
+            he_elevation_ft INTEGER,
+            he_heading_degT INTEGER,
+            he_displaced_threshold_ft INTEGER
+        )",
+    columns: &[
+        "id",
+        "airport_ref",
+        "airport_icao_code",
+        "length_ft",
+        "width_ft",
+        "surface",
+        "lighted",
+        "closed",
+        "le_ident",
+        "le_latitude_deg",
+        "le_longitude_deg",
+        "le_elevation_ft",
+        "le_heading_degT",
+        "le_displaced_threshold_ft",
+        "he_ident",
+        "he_latitude_deg",
+        "he_longitude_deg",
+        "he_elevation_ft",
+        "he_heading_degT",
+        "he_displaced_threshold_ft",
+    ],
+    indexes: &["airport_icao_code"],
+};
+
+const NAVAIDS_TABLE: CsvTable = CsvTable {
+    name: "navaids",
+    label: "navaids",
+    file: super::NAVAID_CSV,
+    schema: "(
+            id INTEGER UNIQUE PRIMARY KEY NOT NULL,
+            filename TEXT NOT NULL,
+            icao_code TEXT NOT NULL,
+            name TEXT,
+            type TEXT,
+            frequency_khz INTEGER,
+            latitude_deg DECIMAL,
+            longitude_deg DECIMAL,
+            elevation_ft INTEGER,
+            iso_country TEXT,
+            dme_frequency_khz INTEGER,
+            dme_channel TEXT,
+            dme_latitude_deg DECIMAL,
+            dme_longitude_deg DECIMAL,
+            dme_elevation_ft INTEGER,
+            slaved_variation_deg INTEGER,
+            magnetic_variation_deg INTEGER,
+            usageType TEXT,
+            power TEXT,
+            associated_airport TEXT
+        )",
+    columns: &[
+        "id",
+        "filename",
+        "icao_code",
+        "name",
+        "type",
+        "frequency_khz",
+        "latitude_deg",
+        "longitude_deg",
+        "elevation_ft",
+        "iso_country",
+        "dme_frequency_khz",
+        "dme_channel",
+        "dme_latitude_deg",
+        "dme_longitude_deg",
+        "dme_elevation_ft",
+        "slaved_variation_deg",
+        "magnetic_variation_deg",
+        "usageType",
+        "power",
+        "associated_airport",
+    ],
+    indexes: &[
+        "name",
+        "filename",
+        "associated_airport",
+        "type",
+        "iso_country",
+    ],
+};
+
+const CSV_TABLES: [&CsvTable; 4] = [
+    &AIRPORTS_TABLE,
+    &AIRPORT_FREQUENCIES_TABLE,
+    &AIRPORT_RUNWAYS_TABLE,
+    &NAVAIDS_TABLE,
+];
+
+/// Runs the closure in a transaction, committed on success and rolled back on error
+fn transaction<T, F>(con: &Connection, f: F) -> SqlResult<T>
+where
+    F: FnOnce() -> SqlResult<T>,
+{
+    con.execute("BEGIN")?;
+    match f() {
+        Ok(value) => {
+            con.execute("COMMIT")?;
+            Ok(value)
+        }
+        Err(err) => {
+            let _ = con.execute("ROLLBACK");
+            Err(err)
+        }
+    }
+}
+
+/// Read-only connections, opened on demand and kept open for reuse
+struct ReaderPool {
+    path: PathBuf,
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl ReaderPool {
+    fn get(&self) -> SqlResult<Connection> {
+        if let Some(connection) = self.idle.lock().expect(ERROR_SQLITE_ACCESS).pop() {
+            return Ok(connection);
+        }
+        let mut connection = Connection::open_with_flags(
+            &self.path,
+            OpenFlags::new().with_read_only().with_no_mutex(),
+        )?;
+        connection.set_busy_timeout(BUSY_TIMEOUT_MS)?;
+        Ok(connection)
+    }
+
+    fn release(&self, connection: Connection) {
+        let mut idle = self.idle.lock().expect(ERROR_SQLITE_ACCESS);
+        if idle.len() < MAX_IDLE_READERS {
+            idle.push(connection);
+        }
+    }
+}
+
+/// SQLite database in WAL mode. Writes go through a single connection, while reads
+/// use a pool of read-only connections and are never blocked by data loads. SQLite
+/// calls are blocking, they run in the blocking thread pool
+pub struct SqliteBackend {
+    writer: Arc<Mutex<Connection>>,
+    readers: Arc<ReaderPool>,
+    /// Database file removed when the backend is dropped, for non persistent databases
+    temporary_file: Option<PathBuf>,
+    status: Mutex<UpdateStatus>,
+}
+
+impl SqliteBackend {
+    pub fn new(path: String) -> SqliteBackend {
+        // In memory databases are private to their connection. A temporary file is
+        // used instead, so that the readers can share it
+        let temporary_file = (path == MEMORY_PATH)
+            .then(|| std::env::temp_dir().join(format!("navdata-{}.db", Uuid::new_v4())));
+        let path = temporary_file
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(path));
+
+        let mut writer = sqlite::open(&path).expect(ERROR_SQLITE_ACCESS);
+        writer
+            .set_busy_timeout(BUSY_TIMEOUT_MS)
+            .expect(ERROR_SQLITE_ACCESS);
+        writer
+            .execute("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")
+            .expect(ERROR_SQLITE_ACCESS);
+        create_tables(&writer).unwrap();
+
+        SqliteBackend {
+            writer: Arc::new(Mutex::new(writer)),
+            readers: Arc::new(ReaderPool {
+                path,
+                idle: Mutex::new(vec![]),
+            }),
+            temporary_file,
+            status: Mutex::new(UpdateStatus::default()),
+        }
+    }
+
+    /// Runs queries on a read-only connection
+    async fn read<T, F>(&self, f: F) -> Result<T, Box<dyn Error>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> SqlResult<T> + Send + 'static,
+    {
+        let readers = self.readers.clone();
+        let result = spawn_blocking(move || {
+            let connection = readers.get()?;
+            let result = f(&connection);
+            readers.release(connection);
+            result
+        })
+        .await?;
+        result.map_err(|err| err as Box<dyn Error>)
+    }
+
+    /// Runs queries on the writer connection
+    async fn write<T, F>(&self, f: F) -> Result<T, Box<dyn Error>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> SqlResult<T> + Send + 'static,
+    {
+        let writer = self.writer.clone();
+        let result = spawn_blocking(move || f(&writer.lock().expect(ERROR_SQLITE_ACCESS))).await?;
+        result.map_err(|err| err as Box<dyn Error>)
+    }
+
+    /// Loads the content of a CSV file into the matching table, recording its sha if given
+    pub async fn load_csv(
+        &self,
+        file: &str,
+        data: String,
+        sha: Option<String>,
+    ) -> Result<(), Box<dyn Error>> {
+        let table = CSV_TABLES
+            .into_iter()
+            .find(|table| table.file == file)
+            .ok_or_else(|| format!("Unknown data file {}", file))?;
+        self.write(move |con| load_table(con, table, &data, sha.as_deref()))
+            .await
+    }
+
+    /// Reloads a table if its file changed. Returns the sha of the loaded data
+    async fn update_table(
+        &self,
+        source: &DataSource,
+        table: &'static CsvTable,
+        sha: Option<&String>,
+    ) -> Result<Option<String>, UpdateError> {
+        let load_error = |err: Box<dyn Error>| UpdateError::Load {
+            file: table.file.to_string(),
+            reason: err.to_string(),
+        };
+
+        let sha = sha
+            .ok_or_else(|| UpdateError::MissingFile(table.file.to_string()))?
+            .clone();
+        let checked_sha = sha.clone();
+        if !self
+            .read(move |con| is_sha_updated(con, table.file, &checked_sha))
+            .await
+            .map_err(load_error)?
+        {
+            info!("{} is up to date", table.file);
+            return Ok(Some(sha));
+        }
+
+        let data = with_retry(&format!("Downloading {}", table.file), || {
+            source.fetch(table.file)
+        })
+        .await
+        .map_err(|reason| UpdateError::Fetch {
+            file: table.file.to_string(),
+            reason,
+        })?;
+        let loaded_sha = sha.clone();
+        self.write(move |con| load_table(con, table, &data, Some(&loaded_sha)))
+            .await
+            .map_err(load_error)?;
+        Ok(Some(sha))
+    }
+}
+
+impl Drop for SqliteBackend {
+    fn drop(&mut self) {
+        if let Some(path) = &self.temporary_file {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+            }
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl NavDataStore for SqliteBackend {
+    async fn get_airport_by_icao_code(
+        &self,
+        icao: String,
+    ) -> Result<Option<Airport>, Box<dyn Error>> {
+        let mut airports = self
+            .read(move |con| airports_by_icao_codes(con, &[icao.to_uppercase()], false))
+            .await?;
+        Ok(airports.pop())
+    }
+
+    async fn get_navaids_by_icao_code(&self, icao: String) -> Result<Vec<Navaid>, Box<dyn Error>> {
+        self.read(move |con| {
+            let mut navaids = vec![];
+            let query = "SELECT * FROM navaids WHERE icao_code=?";
+
+            let icao = icao.to_uppercase();
+
+            let mut statement = con.prepare(query)?;
+            statement.bind((1, icao.as_str()))?;
+
+            while let Ok(State::Row) = statement.next() {
+                navaids.push(read_navaid(&statement)?);
+            }
+            Ok(navaids)
+        })
+        .await
+    }
+
+    async fn search_airport(&self, filter: SearchFilter) -> Result<Vec<Airport>, Box<dyn Error>> {
+        self.read(move |con| {
+            let center = filter.center();
+
+            // First build the query
+            let (mut conditions, mut params) =
+                search_conditions(&filter, &["icao_code", "name", "municipality", "iata_code"]);
+            if let Some(airport_type) = &filter.r#type {
+                conditions.push("type = ?".to_owned());
+                params.push(SqlValue::String(airport_type.to_lowercase()));
+            }
+            let mut query = format!(
+                "SELECT icao_code, latitude_deg, longitude_deg FROM airports{}",
+                where_clause(&conditions)
+            );
+            if center.is_none() {
+                query.push_str(&limit_clause(&filter));
+            }
+
+            // Build and fill the statement
+            let mut statement = con.prepare(query)?;
+            statement.bind(&params[..])?;
+
+            // Execute statement and get the results
+            let mut codes = vec![];
+            while let Ok(State::Row) = statement.next() {
+                let icao_code = statement.read::<String, _>("icao_code")?;
+                let distance = center.map(|(latitude, longitude)| {
+                    super::great_circle_distance_nm(
+                        statement.read::<f64, _>("latitude_deg").unwrap_or_default(),
+                        statement
+                            .read::<f64, _>("longitude_deg")
+                            .unwrap_or_default(),
+                        latitude,
+                        longitude,
+                    )
+                });
+                codes.push((icao_code, distance));
+            }
+
+            // Proximity searchs are ordered by distance, then paginated
+            let codes = match center {
+                Some(_) => sort_by_distance(codes, |(_, distance)| *distance, &filter),
+                None => codes,
+            };
+
+            let (codes, distances): (Vec<String>, Vec<Option<f64>>) = codes.into_iter().unzip();
+            let mut airports = airports_by_icao_codes(con, &codes, filter.skip_details)?;
+            for airport in airports.iter_mut() {
+                airport.distance_nm = codes
+                    .iter()
+                    .position(|code| *code == airport.icao_code)
+                    .and_then(|position| distances[position]);
+            }
+            Ok(airports)
+        })
+        .await
+    }
+
+    async fn search_navaid(&self, filter: SearchFilter) -> Result<Vec<Navaid>, Box<dyn Error>> {
+        self.read(move |con| {
+            let center = filter.center();
+
+            // First build the query
+            let (mut conditions, mut params) =
+                search_conditions(&filter, &["icao_code", "name", "associated_airport"]);
+            if let Some(navaid_type) = &filter.r#type {
+                conditions.push("type = ?".to_owned());
+                params.push(SqlValue::String(navaid_type.to_uppercase()));
+            }
+            let mut query = format!("SELECT * FROM navaids{}", where_clause(&conditions));
+            if center.is_none() {
+                query.push_str(&limit_clause(&filter));
+            }
+
+            // Build and fill the statement
+            let mut statement = con.prepare(query)?;
+            statement.bind(&params[..])?;
+
+            // Execute statement and get the results
+            let mut navaids = vec![];
+            while let Ok(State::Row) = statement.next() {
+                navaids.push(read_navaid(&statement)?);
+            }
+
+            // Proximity searchs are ordered by distance, then paginated
+            if let Some((latitude, longitude)) = center {
+                for navaid in navaids.iter_mut() {
+                    navaid.distance_nm = Some(navaid.location.distance_nm(latitude, longitude));
+                }
+                navaids = sort_by_distance(navaids, |navaid| navaid.distance_nm, &filter);
+            }
+            Ok(navaids)
+        })
+        .await
+    }
+
+    /// Reloads the files whose sha changed. A failing file does not prevent the
+    /// others from being updated
+    async fn load(&self, source: &DataSource) -> Result<(), UpdateError> {
+        let shas = with_retry("Listing data files", || source.get_list_of_sha())
+            .await
+            .map_err(UpdateError::Source)?;
+
+        let mut result = Ok(());
+        for table in CSV_TABLES {
+            let file_result = self.update_table(source, table, shas.get(table.file)).await;
+            if let Err(err) = &file_result {
+                error!("{}", err);
+                result = Err(err.clone());
+            }
+            self.status
+                .lock()
+                .expect(ERROR_UPDATE_STATUS_ACCESS)
+                .record_file(table.file, &file_result);
+        }
+        result
+    }
+
+    /// Data files loaded in the database, with their row count
+    async fn loaded_files(&self) -> Result<Vec<LoadedFile>, Box<dyn Error>> {
+        self.read(|con| {
+            let mut loaded = vec![];
+            for table in CSV_TABLES {
+                let query = format!(
+                    "SELECT sha, date, (SELECT count(*) FROM {}) as rows FROM data_last_update WHERE file = ?",
+                    table.name
+                );
+                let mut statement = con.prepare(query)?;
+                statement.bind((1, table.file))?;
+                if let Ok(State::Row) = statement.next() {
+                    loaded.push(LoadedFile {
+                        file: table.file.to_string(),
+                        sha: statement.read::<String, _>("sha")?,
+                        date: statement
+                            .read::<Option<i64>, _>("date")?
+                            .unwrap_or_default(),
+                        rows: statement.read::<i64, _>("rows")?,
+                    });
+                }
+            }
+            Ok(loaded)
+        })
+        .await
+    }
+
+    fn update_status(&self) -> &Mutex<UpdateStatus> {
+        &self.status
+    }
+}
+
+fn create_tables(con: &Connection) -> SqlResult<()> {
+    info!("Start database creation");
+    con.execute(
+        "CREATE TABLE IF NOT EXISTS data_last_update (
+        file TEXT UNIQUE PRIMARY KEY NOT NULL,
+        sha TEXT NOT NULL,
+        date INTEGER
+        );",
+    )?;
+    for table in CSV_TABLES {
+        con.execute(format!(
+            "CREATE TABLE IF NOT EXISTS {} {};",
+            table.name, table.schema
+        ))?;
+        con.execute(table.create_indexes())?;
+    }
+    info!("Database fully created");
+    Ok(())
+}
+
+/// Loads CSV data into a shadow table, then swaps it with the live table in a
+/// single transaction, together with the file sha if given. On failure, the live
+/// table is left untouched
+fn load_table(con: &Connection, table: &CsvTable, data: &str, sha: Option<&str>) -> SqlResult<()> {
+    let shadow = format!("{}_new", table.name);
+
+    if let Err(err) = fill_table(con, table, &shadow, data) {
+        let _ = con.execute(format!("DROP TABLE IF EXISTS {}", shadow));
+        return Err(err);
+    }
+
+    transaction(con, || {
+        con.execute(format!(
+            "DROP TABLE {}; ALTER TABLE {} RENAME TO {};",
+            table.name, shadow, table.name
+        ))?;
+        con.execute(table.create_indexes())?;
+        if let Some(sha) = sha {
+            let query =
+                "REPLACE INTO data_last_update (file, sha, date) VALUES (?, ?, unixepoch())";
+            let mut statement = con.prepare(query)?;
+            statement.bind((1, table.file))?;
+            statement.bind((2, sha))?;
+            statement.next()?;
+        }
+        Ok(())
+    })?;
+
+    let query = format!("SELECT count(*) as count from {}", table.name);
+    con.iterate(query, |result| {
+        for &(_, value) in result.iter() {
+            info!("{} {} loaded", value.unwrap_or_default(), table.label);
+        }
+        true
+    })?;
+
+    Ok(())
+}
+
+/// Creates a fresh copy of the table and fills it with the CSV data, by batches
+/// of `INSERT_BATCH_SIZE` rows
+fn fill_table(con: &Connection, table: &CsvTable, name: &str, data: &str) -> SqlResult<()> {
+    con.execute(format!(
+        "DROP TABLE IF EXISTS {}; CREATE TABLE {} {};",
+        name, name, table.schema
+    ))?;
+
+    let query = format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        name,
+        table.columns.join(", "),
+        vec!["?"; table.columns.len()].join(", ")
+    );
+    let mut reader = csv::ReaderBuilder::new().from_reader(data.as_bytes());
+    let mut records = reader.records();
+    loop {
+        let count = transaction(con, || {
+            let mut statement = con.prepare(&query)?;
+            let mut count = 0;
+            for result in records.by_ref().take(INSERT_BATCH_SIZE) {
+                let record = result?;
+                statement.reset()?;
+                for i in 0..table.columns.len() {
+                    statement.bind((i + 1, record.get(i).ok_or(CSV_FORMAT_ERROR)?))?;
+                }
+                statement.next()?;
+                count += 1;
+            }
+            Ok(count)
+        })?;
+        if count < INSERT_BATCH_SIZE {
+            return Ok(());
+        }
+    }
+}
+
+/// Returns true if the sha differs from the one of the last successful load of the file
+fn is_sha_updated(con: &Connection, file: &str, sha: &str) -> SqlResult<bool> {
+    let query = "SELECT count(*) as count FROM data_last_update WHERE file = ? AND sha = ?";
+
+    let mut s = con.prepare(query)?;
+    s.bind((1, file))?;
+    s.bind((2, sha))?;
+
+    s.next()?;
+    let count = s.read::<i64, _>("count")?;
+    Ok(count == 0)
+}
+
+/// Airports with the given codes, in the same order. Their runways, frequencies and
+/// navaids are loaded with one query each, unless skipped
+fn airports_by_icao_codes(
+    con: &Connection,
+    codes: &[String],
+    skip_details: bool,
+) -> SqlResult<Vec<Airport>> {
+    let mut airports = rows_by_codes(con, "airports", "icao_code", codes, read_airport)?;
+    airports.sort_by_key(|airport| codes.iter().position(|code| *code == airport.icao_code));
+    if !skip_details {
+        add_details(con, &mut airports)?;
+    }
+    Ok(airports)
+}
+
+/// Adds their runways, frequencies and navaids to the airports
+fn add_details(con: &Connection, airports: &mut [Airport]) -> SqlResult<()> {
+    let codes = airports
+        .iter()
+        .map(|airport| airport.icao_code.clone())
+        .collect::<Vec<String>>();
+    let mut runways = group_by_airport(
+        rows_by_codes(
+            con,
+            "airport_runways",
+            "airport_icao_code",
+            &codes,
+            read_runway,
+        )?,
+        |runway| &runway.airport_icao_code,
+    );
+    let mut frequencies = group_by_airport(
+        rows_by_codes(
+            con,
+            "airport_frequencies",
+            "airport_icao_code",
+            &codes,
+            read_frequency,
+        )?,
+        |frequency| &frequency.airport_icao_code,
+    );
+    let mut navaids = group_by_airport(
+        rows_by_codes(con, "navaids", "associated_airport", &codes, read_navaid)?,
+        |navaid| &navaid.associated_airport,
+    );
+    for airport in airports.iter_mut() {
+        airport.runways = runways.remove(&airport.icao_code).unwrap_or_default();
+        airport.frequencies = frequencies.remove(&airport.icao_code).unwrap_or_default();
+        airport.navaids = navaids.remove(&airport.icao_code).unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// Rows of the table whose column is one of the codes, in a single query
+fn rows_by_codes<T>(
+    con: &Connection,
+    table: &str,
+    column: &str,
+    codes: &[String],
+    read: fn(&Statement) -> SqlResult<T>,
+) -> SqlResult<Vec<T>> {
+    if codes.is_empty() {
+        return Ok(vec![]);
+    }
+    let query = format!(
+        "SELECT * FROM {} WHERE {} IN ({}) ORDER BY id",
+        table,
+        column,
+        vec!["?"; codes.len()].join(", ")
+    );
+    let params = codes
+        .iter()
+        .map(|code| SqlValue::String(code.clone()))
+        .collect::<Vec<SqlValue>>();
+    let mut statement = con.prepare(query)?;
+    statement.bind(&params[..])?;
+
+    let mut rows = vec![];
+    while let Ok(State::Row) = statement.next() {
+        rows.push(read(&statement)?);
+    }
+    Ok(rows)
+}
+
+fn group_by_airport<T>(items: Vec<T>, airport: fn(&T) -> &String) -> HashMap<String, Vec<T>> {
+    let mut groups: HashMap<String, Vec<T>> = HashMap::new();
+    for item in items {
+        groups.entry(airport(&item).clone()).or_default().push(item);
+    }
+    groups
+}
+
+fn read_airport(statement: &Statement) -> SqlResult<Airport> {
+    Ok(Airport {
+        id: statement.read::<i64, _>("id")?,
+        icao_code: statement.read::<String, _>("icao_code")?,
+        r#type: AirportType::from_str(statement.read::<String, _>("type")?.as_str()).unwrap(),
+        name: statement.read::<String, _>("name")?,
+        location: LocationPoint {
+            r#type: LocationType::Point,
+            coordinates: vec![
+                statement.read::<f64, _>("longitude_deg")?,
+                statement.read::<f64, _>("latitude_deg")?,
+            ],
+        },
+        elevation_ft: statement.read::<i64, _>("elevation_ft")?,
+        continent: statement.read::<String, _>("continent")?,
+        iso_country: statement.read::<String, _>("iso_country")?,
+        iso_region: statement.read::<String, _>("iso_region")?,
+        municipality: statement.read::<String, _>("municipality")?,
+        scheduled_service: statement.read::<String, _>("scheduled_service")?,
+        gps_code: statement.read::<String, _>("gps_code")?,
+        iata_code: statement.read::<String, _>("iata_code")?,
+        local_code: statement.read::<String, _>("local_code")?,
+        home_link: statement.read::<String, _>("home_link")?,
+        wikipedia_link: statement.read::<String, _>("wikipedia_link")?,
+        keywords: statement.read::<String, _>("keywords")?,
+        ..Default::default()
+    })
+}
+
+fn read_runway(statement: &Statement) -> SqlResult<Runway> {
+    Ok(Runway {
+        id: statement.read::<i64, _>("id")?,
+        airport_id: statement.read::<i64, _>("airport_ref")?,
+        airport_icao_code: statement.read::<String, _>("airport_icao_code")?,
+        length_ft: statement.read::<i64, _>("length_ft")?,
+        width_ft: statement.read::<i64, _>("width_ft")?,
+        surface: statement.read::<i64, _>("surface")?,
+        lighted: statement.read::<i64, _>("lighted")?,
+        closed: statement.read::<i64, _>("closed")?,
+        le_ident: statement.read::<String, _>("le_ident")?,
+        le_location: LocationPoint {
+            r#type: LocationType::Point,
+            coordinates: vec![
+                statement.read::<f64, _>("le_longitude_deg")?,
+                statement.read::<f64, _>("le_latitude_deg")?,
+            ],
+        },
+        le_elevation_ft: statement.read::<i64, _>("le_elevation_ft")?,
+        le_heading_deg_t: statement.read::<i64, _>("le_heading_degT")?,
+        le_displaced_threshold_ft: statement.read::<i64, _>("le_displaced_threshold_ft")?,
+        he_ident: statement.read::<String, _>("he_ident")?,
+        he_location: LocationPoint {
+            r#type: LocationType::Point,
+            coordinates: vec![
+                statement.read::<f64, _>("he_longitude_deg")?,
+                statement.read::<f64, _>("he_latitude_deg")?,
+            ],
+        },
+        he_elevation_ft: statement.read::<i64, _>("he_elevation_ft")?,
+        he_heading_deg_t: statement.read::<i64, _>("he_heading_degT")?,
+        he_displaced_threshold_ft: statement.read::<i64, _>("he_displaced_threshold_ft")?,
+    })
+}
+
+fn read_frequency(statement: &Statement) -> SqlResult<Frequency> {
+    Ok(Frequency {
+        id: statement.read::<i64, _>("id")?,
+        airport_id: statement.read::<i64, _>("airport_ref")?,
+        airport_icao_code: statement.read::<String, _>("airport_icao_code")?,
+        description: statement.read::<String, _>("description")?,
+        frequency_mhz: statement.read::<f64, _>("frequency_mhz")?,
+        r#type: FrequencyType::from_str(statement.read::<String, _>("type")?.as_str()).unwrap(),
+        raw_type: statement.read::<String, _>("type")?,
+    })
+}
+
+/// SQL conditions and parameters of the criteria shared by all searchs. Text search
+/// is done on the given columns
+fn search_conditions(
+    filter: &SearchFilter,
+    search_columns: &[&str],
+) -> (Vec<String>, Vec<SqlValue>) {
+    let mut conditions = vec![];
+    let mut params = vec![];
+    if let Some(country) = &filter.country {
+        conditions.push("iso_country = ?".to_owned());
+        params.push(SqlValue::String(country.to_uppercase()));
+    }
+    if let Some(search) = &filter.search {
+        let likes = search_columns
+            .iter()
+            .map(|column| format!("{} LIKE '%' || ? || '%'", column))
+            .collect::<Vec<String>>();
+        conditions.push(format!("({})", likes.join(" OR ")));
+        params.extend(vec![SqlValue::String(search.clone()); search_columns.len()]);
+    }
+    if let Some((latitude, longitude)) = filter.center() {
+        let (condition, bounds) = proximity_condition(latitude, longitude, filter.radius_nm());
+        conditions.push(condition);
+        params.extend(bounds);
+    }
+    if let Some(bbox) = &filter.bbox {
+        let (condition, bounds) = bbox_condition(bbox);
+        conditions.push(condition);
+        params.extend(bounds);
+    }
+    (conditions, params)
+}
+
+fn where_clause(conditions: &[String]) -> String {
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    }
+}
+
+fn limit_clause(filter: &SearchFilter) -> String {
+    format!(" LIMIT {}, {}", filter.offset(), filter.page_size())
+}
+
+fn bbox_condition(bbox: &BoundingBox) -> (String, Vec<SqlValue>) {
+    let params = vec![
+        SqlValue::Float(bbox.min_lat),
+        SqlValue::Float(bbox.max_lat),
+        SqlValue::Float(bbox.min_lon),
+        SqlValue::Float(bbox.max_lon),
+    ];
+    match bbox.crosses_antimeridian() {
+        true => (
+            "latitude_deg BETWEEN ? AND ? AND (longitude_deg >= ? OR longitude_deg <= ?)"
+                .to_owned(),
+            params,
+        ),
+        false => (
+            "latitude_deg BETWEEN ? AND ? AND longitude_deg BETWEEN ? AND ?".to_owned(),
+            params,
+        ),
+    }
+}
+
+/// Bounding box prefilter of a proximity search, as a SQL condition and its parameters.
+/// Exact distances are computed afterwards, SQLite has no trigonometric functions by default
+fn proximity_condition(latitude: f64, longitude: f64, radius_nm: f64) -> (String, Vec<SqlValue>) {
+    // One minute of latitude is one nautical mile
+    let delta_lat = radius_nm / 60.0;
+    let min_lat = latitude - delta_lat;
+    let max_lat = latitude + delta_lat;
+
+    // Longitudes are unbounded when the circle includes a pole
+    if min_lat <= -90.0 || max_lat >= 90.0 {
+        return (
+            "latitude_deg BETWEEN ? AND ?".to_owned(),
+            vec![SqlValue::Float(min_lat), SqlValue::Float(max_lat)],
+        );
+    }
+    let delta_lon = delta_lat / max_lat.abs().max(min_lat.abs()).to_radians().cos();
+    if delta_lon >= 180.0 {
+        return (
+            "latitude_deg BETWEEN ? AND ?".to_owned(),
+            vec![SqlValue::Float(min_lat), SqlValue::Float(max_lat)],
+        );
+    }
+    let min_lon = longitude - delta_lon;
+    let max_lon = longitude + delta_lon;
+    bbox_condition(&BoundingBox {
+        min_lon: (min_lon + 540.0) % 360.0 - 180.0,
+        min_lat,
+        max_lon: (max_lon + 540.0) % 360.0 - 180.0,
+        max_lat,
+    })
+}
+
+/// Keeps the items within the search radius, ordered by distance, and returns the requested page
+fn sort_by_distance<T, F>(mut items: Vec<T>, distance: F, filter: &SearchFilter) -> Vec<T>
+where
+    F: Fn(&T) -> Option<f64>,
+{
+    let radius_nm = filter.radius_nm();
+    items.retain(|item| distance(item).unwrap_or(f64::MAX) <= radius_nm);
+    items.sort_by(|a, b| {
+        distance(a)
+            .unwrap_or(f64::MAX)
+            .total_cmp(&distance(b).unwrap_or(f64::MAX))
+    });
+    items
+        .into_iter()
+        .skip(filter.offset() as usize)
+        .take(filter.page_size() as usize)
+        .collect()
+}
+
+fn read_navaid(statement: &Statement) -> SqlResult<Navaid> {
+    Ok(Navaid {
+        id: statement.read::<i64, _>("id")?,
+        filename: statement.read::<String, _>("filename")?,
+        icao_code: statement.read::<String, _>("icao_code")?,
+        name: statement.read::<String, _>("name")?,
+        r#type: NavaidType::from_str(statement.read::<String, _>("type")?.as_str()).unwrap(),
+        frequency_khz: statement.read::<i64, _>("frequency_khz")?,
+        location: LocationPoint {
+            r#type: LocationType::Point,
+            coordinates: vec![
+                statement.read::<f64, _>("longitude_deg")?,
+                statement.read::<f64, _>("latitude_deg")?,
+            ],
+        },
+        elevation_ft: statement.read::<i64, _>("elevation_ft")?,
+        iso_country: statement.read::<String, _>("iso_country")?,
+        dme_frequency_khz: statement.read::<i64, _>("dme_frequency_khz")?,
+        dme_channel: statement.read::<String, _>("dme_channel")?,
+        dme_location: LocationPoint {
+            r#type: LocationType::Point,
+            coordinates: vec![
+                statement.read::<f64, _>("dme_longitude_deg")?,
+                statement.read::<f64, _>("dme_latitude_deg")?,
+            ],
+        },
+        dme_elevation_ft: statement.read::<i64, _>("dme_elevation_ft")?,
+        slaved_variation_deg: statement.read::<i64, _>("slaved_variation_deg")?,
+        magnetic_variation_deg: statement.read::<i64, _>("magnetic_variation_deg")?,
+        usage_type: statement.read::<String, _>("usageType")?,
+        power: statement.read::<String, _>("power")?,
+        associated_airport: statement.read::<String, _>("associated_airport")?,
+        distance_nm: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn floats(params: &[SqlValue]) -> Vec<f64> {
+        params
+            .iter()
+            .map(|param| match param {
+                SqlValue::Float(value) => *value,
+                _ => panic!("unexpected parameter"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn proximity_prefilter_is_a_box_around_the_point() {
+        let (condition, params) = proximity_condition(45.0, 5.0, 60.0);
+        assert_eq!(
+            condition,
+            "latitude_deg BETWEEN ? AND ? AND longitude_deg BETWEEN ? AND ?"
+        );
+        let [min_lat, max_lat, min_lon, max_lon] = floats(&params)[..] else {
+            panic!("expected four bounds");
+        };
+        assert_eq!((min_lat, max_lat), (44.0, 46.0));
+        // The box is widened by the longitude span at its highest latitude
+        assert!(min_lon < 5.0 - 1.0 / 45f64.to_radians().cos());
+        assert!(max_lon > 5.0 + 1.0 / 45f64.to_radians().cos());
+    }
+
+    #[test]
+    fn proximity_prefilter_contains_every_point_within_the_radius() {
+        let (latitude, longitude, radius_nm) = (50.0, 10.0, 300.0);
+        let (_, params) = proximity_condition(latitude, longitude, radius_nm);
+        let [min_lat, max_lat, min_lon, max_lon] = floats(&params)[..] else {
+            panic!("expected four bounds");
+        };
+        for bearing in (0..360).step_by(5) {
+            // Walk towards the circle edge along a bearing
+            let bearing = (bearing as f64).to_radians();
+            let angle = radius_nm / crate::db::EARTH_RADIUS_NM;
+            let (lat1, lon1) = (latitude.to_radians(), longitude.to_radians());
+            let lat2 = (lat1.sin() * angle.cos() + lat1.cos() * angle.sin() * bearing.cos()).asin();
+            let lon2 = lon1
+                + (bearing.sin() * angle.sin() * lat1.cos())
+                    .atan2(angle.cos() - lat1.sin() * lat2.sin());
+            let (lat2, lon2) = (lat2.to_degrees(), lon2.to_degrees());
+            assert!((min_lat..=max_lat).contains(&lat2), "latitude {}", lat2);
+            assert!((min_lon..=max_lon).contains(&lon2), "longitude {}", lon2);
+        }
+    }
+
+    #[test]
+    fn proximity_prefilter_wraps_around_the_antimeridian() {
+        let (condition, params) = proximity_condition(0.0, 179.5, 60.0);
+        assert_eq!(
+            condition,
+            "latitude_deg BETWEEN ? AND ? AND (longitude_deg >= ? OR longitude_deg <= ?)"
+        );
+        let [_, _, min_lon, max_lon] = floats(&params)[..] else {
+            panic!("expected four bounds");
+        };
+        assert!(min_lon > 178.0 && min_lon < 179.5);
+        assert!(max_lon < -179.0 && max_lon > -180.0);
+
+        let (_, params) = proximity_condition(0.0, -179.5, 60.0);
+        let [_, _, min_lon, max_lon] = floats(&params)[..] else {
+            panic!("expected four bounds");
+        };
+        assert!(min_lon > 179.0 && max_lon < -178.0);
+    }
+
+    #[test]
+    fn proximity_prefilter_ignores_longitudes_around_the_poles() {
+        let (condition, params) = proximity_condition(89.5, 0.0, 60.0);
+        assert_eq!(condition, "latitude_deg BETWEEN ? AND ?");
+        assert_eq!(floats(&params), vec![88.5, 90.5]);
+    }
+
+    #[test]
+    fn bounding_box_across_the_antimeridian_matches_either_side() {
+        let bbox = "170,-10,-170,10".parse::<BoundingBox>().unwrap();
+        let (condition, params) = bbox_condition(&bbox);
+        assert_eq!(
+            condition,
+            "latitude_deg BETWEEN ? AND ? AND (longitude_deg >= ? OR longitude_deg <= ?)"
+        );
+        assert_eq!(floats(&params), vec![-10.0, 10.0, 170.0, -170.0]);
+    }
+
+    #[test]
+    fn limit_clause_skips_the_previous_pages() {
+        let filter = SearchFilter {
+            page: Some(2),
+            limit: Some(10),
+            ..Default::default()
+        };
+        assert_eq!(limit_clause(&filter), " LIMIT 20, 10");
+    }
+}
@@ -0,0 +1,290 @@
+use crate::messages::ERROR_UPDATE_STATUS_ACCESS;
+use derive_more::Display;
+use log::{error, info, warn};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::future::Future;
+use std::sync::{Mutex, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::time::{sleep, Duration};
+
+use super::CSV_FILES;
+
+/// Delay before the first retry of a failed operation. Doubled at each new attempt
+const RETRY_INITIAL_DELAY: Duration = Duration::from_secs(10);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(600);
+const RETRY_MAX_ATTEMPTS: u32 = 6;
+
+#[derive(Clone, Debug, Display)]
+pub enum UpdateError {
+    #[display(fmt = "Could not list data files : {}", _0)]
+    Source(String),
+    #[display(fmt = "{} not found in data source", _0)]
+    MissingFile(String),
+    #[display(fmt = "Could not download {} : {}", file, reason)]
+    Fetch { file: String, reason: String },
+    #[display(fmt = "Could not load {} : {}", file, reason)]
+    Load { file: String, reason: String },
+    #[display(fmt = "A data refresh is already running")]
+    AlreadyRunning,
+}
+
+impl Error for UpdateError {}
+
+/// A data file loaded in the database, as recorded in data_last_update
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LoadedFile {
+    pub file: String,
+    pub sha: String,
+    /// Unix timestamp of the load
+    pub date: i64,
+    pub rows: i64,
+}
+
+/// Version of the dataset currently served
+#[derive(Clone, Debug, Serialize)]
+pub struct DatasetMeta {
+    /// Identifies the set of loaded files. Changes whenever one of them is reloaded
+    /// with a different content
+    pub version: String,
+    /// Unix timestamp of the most recent load
+    pub last_update: Option<i64>,
+    pub files: Vec<LoadedFile>,
+}
+
+impl From<Vec<LoadedFile>> for DatasetMeta {
+    fn from(mut files: Vec<LoadedFile>) -> Self {
+        files.sort_by(|a, b| a.file.cmp(&b.file));
+        let mut hasher = Sha256::new();
+        for loaded_file in &files {
+            hasher.update(format!("{}:{};", loaded_file.file, loaded_file.sha));
+        }
+        DatasetMeta {
+            version: format!("{:x}", hasher.finalize())[..16].to_string(),
+            last_update: files.iter().map(|loaded_file| loaded_file.date).max(),
+            files,
+        }
+    }
+}
+
+/// Update outcome of a data file
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct FileStatus {
+    /// Sha of the loaded data, when known
+    pub sha: Option<String>,
+    /// Unix timestamp of the load of the data currently served
+    pub loaded_at: Option<i64>,
+    pub rows: Option<i64>,
+    /// Unix timestamp of the last successful load
+    pub last_success: Option<i64>,
+    pub last_error: Option<String>,
+    /// Unix timestamp of the last error
+    pub last_error_date: Option<i64>,
+}
+
+/// Outcome of the data updates, for operators
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct UpdateStatus {
+    /// True while an update cycle is in progress
+    pub running: bool,
+    /// Unix timestamp of the last update cycle without any error
+    pub last_success: Option<i64>,
+    pub last_error: Option<String>,
+    /// Unix timestamp of the last error
+    pub last_error_date: Option<i64>,
+    pub files: BTreeMap<String, FileStatus>,
+}
+
+impl UpdateStatus {
+    pub fn record_file(&mut self, file: &str, result: &Result<Option<String>, UpdateError>) {
+        let status = self.files.entry(file.to_string()).or_default();
+        match result {
+            Ok(sha) => {
+                if sha.is_some() {
+                    status.sha = sha.clone();
+                }
+                status.last_success = Some(now());
+            }
+            Err(err) => {
+                status.last_error = Some(err.to_string());
+                status.last_error_date = Some(now());
+            }
+        }
+    }
+
+    /// Status completed with the files currently loaded in the database
+    pub fn report(&self, loaded: Vec<LoadedFile>) -> UpdateStatus {
+        let mut report = self.clone();
+        for file in CSV_FILES {
+            report.files.entry(file.to_string()).or_default();
+        }
+        for loaded_file in loaded {
+            let status = report.files.entry(loaded_file.file).or_default();
+            status.sha = Some(loaded_file.sha);
+            status.loaded_at = Some(loaded_file.date);
+            status.rows = Some(loaded_file.rows);
+        }
+        report
+    }
+
+    pub fn record_cycle(&mut self, result: &Result<(), UpdateError>) {
+        match result {
+            Ok(()) => self.last_success = Some(now()),
+            Err(err) => {
+                self.last_error = Some(err.to_string());
+                self.last_error_date = Some(now());
+            }
+        }
+    }
+}
+
+/// An update cycle in progress. The cycle is marked as ended when dropped, so that
+/// a cycle that panicked or was cancelled does not prevent the next ones
+pub struct RunningCycle<'a> {
+    status: &'a Mutex<UpdateStatus>,
+}
+
+impl Drop for RunningCycle<'_> {
+    fn drop(&mut self) {
+        let mut status = self.status.lock().unwrap_or_else(PoisonError::into_inner);
+        status.running = false;
+    }
+}
+
+/// Marks an update cycle as started. Fails if one is already running
+pub fn start_cycle(status: &Mutex<UpdateStatus>) -> Result<RunningCycle<'_>, UpdateError> {
+    let mut running = status.lock().expect(ERROR_UPDATE_STATUS_ACCESS);
+    if running.running {
+        return Err(UpdateError::AlreadyRunning);
+    }
+    running.running = true;
+    Ok(RunningCycle { status })
+}
+
+/// Records and logs the outcome of an update cycle
+pub fn end_of_cycle(cycle: RunningCycle, result: &Result<(), UpdateError>) {
+    let mut status = cycle.status.lock().expect(ERROR_UPDATE_STATUS_ACCESS);
+    status.running = false;
+    status.record_cycle(result);
+    info!(
+        "Update status : {}",
+        serde_json::to_string(&*status).unwrap_or_default()
+    );
+    match result {
+        Ok(()) => info!("Database fully reloaded"),
+        Err(err) => error!("Database reload failed : {}", err),
+    }
+}
+
+/// Current unix timestamp
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Network failures and server errors may go away, local files, parse errors and
+/// client errors will not
+fn is_transient(err: &(dyn Error + 'static)) -> bool {
+    match err.downcast_ref::<reqwest::Error>() {
+        Some(err) => match err.status() {
+            Some(status) => status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS,
+            None => err.is_timeout() || err.is_connect() || err.is_request() || err.is_body(),
+        },
+        None => false,
+    }
+}
+
+/// Runs the operation until it succeeds, with an exponential backoff between attempts.
+/// Only transient errors are retried. Returns the description of the last error
+pub async fn with_retry<T, F, Fut>(operation: &str, mut f: F) -> Result<T, String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Box<dyn Error>>>,
+{
+    let mut delay = RETRY_INITIAL_DELAY;
+    let mut attempt = 1;
+    loop {
+        match f().await {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < RETRY_MAX_ATTEMPTS && is_transient(err.as_ref()) => {
+                warn!(
+                    "{} failed (attempt {}/{}) : {}. Retrying in {}s",
+                    operation,
+                    attempt,
+                    RETRY_MAX_ATTEMPTS,
+                    err,
+                    delay.as_secs()
+                );
+                sleep(delay).await;
+                delay = (delay * 2).min(RETRY_MAX_DELAY);
+                attempt += 1;
+            }
+            Err(err) => return Err(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::io::{self, Read, Write};
+    use std::net::TcpListener;
+
+    /// Error of a request to a server answering with the given status
+    async fn http_error(status: u16) -> reqwest::Error {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/airports.csv", server.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = server.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            let answer = format!("HTTP/1.1 {} Error\r\nContent-Length: 0\r\n\r\n", status);
+            stream.write_all(answer.as_bytes()).unwrap();
+        });
+        let response = reqwest::get(url).await.unwrap();
+        response.error_for_status().unwrap_err()
+    }
+
+    #[test]
+    fn dropped_cycles_no_longer_block_the_next_ones() {
+        let status = Mutex::new(UpdateStatus::default());
+        let cycle = start_cycle(&status).unwrap();
+        assert!(matches!(
+            start_cycle(&status),
+            Err(UpdateError::AlreadyRunning)
+        ));
+        // Such as a refresh cancelled before its end
+        drop(cycle);
+        end_of_cycle(start_cycle(&status).unwrap(), &Ok(()));
+        assert!(!status.lock().unwrap().running);
+        assert!(start_cycle(&status).is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn network_and_server_errors_are_transient() {
+        let refused = reqwest::get("http://127.0.0.1:1/").await.unwrap_err();
+        assert!(is_transient(&refused));
+        assert!(is_transient(&http_error(503).await));
+        assert!(is_transient(&http_error(429).await));
+        assert!(!is_transient(&http_error(404).await));
+        assert!(!is_transient(&http_error(403).await));
+        assert!(!is_transient(&io::Error::from(io::ErrorKind::NotFound)));
+    }
+
+    #[actix_rt::test]
+    async fn local_errors_are_not_retried() {
+        let attempts = Cell::new(0);
+        let result: Result<(), String> = with_retry("Reading airports.csv", || {
+            attempts.set(attempts.get() + 1);
+            async { Err(io::Error::from(io::ErrorKind::NotFound).into()) }
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 1);
+    }
+}
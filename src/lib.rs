@@ -0,0 +1,40 @@
+//! Airports, runways, frequencies and navaids from the OurAirports dataset.
+//!
+//! Data is read from a [`DataSource`], the OurAirports GitHub repository by default,
+//! and stored in one of the backends implementing [`NavDataStore`]. The stores are
+//! queried by ICAO code or with a [`SearchFilter`].
+//!
+//! ```no_run
+//! use navdata::{open_backend, BackendType, DataSource, SearchFilter};
+//!
+//! async fn closest_airports() -> Result<(), Box<dyn std::error::Error>> {
+//!     let store = open_backend(BackendType::MEMORY, String::new()).await;
+//!     store.refresh(&DataSource::default()).await?;
+//!
+//!     let filter = SearchFilter {
+//!         latitude: Some(48.85),
+//!         longitude: Some(2.35),
+//!         limit: Some(5),
+//!         ..Default::default()
+//!     };
+//!     for airport in store.search_airport(filter).await? {
+//!         println!("{} {:?}", airport.icao_code, airport.distance_nm);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! The `nav_data` binary serves this API over HTTP.
+
+pub mod config;
+pub mod db;
+pub mod messages;
+
+pub use db::snapshot::Snapshot;
+pub use db::source::{DataSource, RemoteSource};
+pub use db::update::{DatasetMeta, LoadedFile, UpdateError, UpdateStatus};
+pub use db::{
+    great_circle_distance_nm, open_backend, Airport, AirportType, BackendType, BoundingBox,
+    Frequency, FrequencyType, LocationPoint, NavDataStore, Navaid, NavaidType, Runway,
+    SearchFilter,
+};
@@ -1,68 +1,204 @@
 mod app;
-use actix_cors::Cors;
 use actix_web::{middleware::Logger, web, App, HttpServer};
-use app::config::Config;
-use app::db::{periodical_update, AppState, BackendType, DatabaseBackend};
+use app::security::reload::watch_config;
 use app::security::simple_token::SimpleToken;
-use clap::Parser;
+use app::security::{Security, SharedSecurity};
+use arc_swap::ArcSwap;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use log::{error, info};
+use navdata::config::{Config, ConfigError};
+use navdata::db::export::{all_airports, all_navaids, write_items, ExportFormat};
+use navdata::db::schedule::{Schedule, StartupMode};
+use navdata::db::snapshot::Snapshot;
+use navdata::db::source::DataSource;
+use navdata::db::update::UpdateError;
+use navdata::db::{open_backend, periodical_update, AppState, BackendType, SearchFilter};
+use serde::Serialize;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::PathBuf;
+use std::sync::Arc;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-struct Args {
+struct Cli {
+    /// path to YAML config file
+    #[arg(short, long, global = true)]
+    config: Option<PathBuf>,
+
+    /// loglevel. 0 for error, 1 for warn, 2 for info, 3 for debug. RUST_LOG takes precedence
+    #[arg(short, long, global = true, default_value_t = 2)]
+    loglevel: u8,
+
+    /// Database backend : SQLITE, MONGODB, POSTGRES or MEMORY
+    #[arg(short, long, global = true)]
+    backend: Option<BackendType>,
+
+    /// Database path, or URL for MongoDB and PostgreSQL. Use ":memory:" for an in memory SQLite database
+    #[arg(short, long, global = true)]
+    db_path: Option<String>,
+
+    /// Local directory, .tar.zst or .zip archive or .navdata.zst snapshot holding the CSV files,
+    /// for offline imports
+    #[arg(long, global = true)]
+    data_path: Option<PathBuf>,
+
+    /// Runs the server when omitted
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Runs the HTTP server
+    Serve(ServeArgs),
+    /// Loads the database from the data source, then exits
+    Import,
+    /// Exports the database content, or a snapshot of the data source
+    Export(ExportArgs),
+    /// One-off lookups, printed as JSON. An empty database is loaded first
+    #[command(subcommand)]
+    Query(Query),
+}
+
+#[derive(Args, Debug)]
+struct ServeArgs {
     /// HTTP Server host
-    #[arg(short, long, default_value = "127.0.0.1")]
-    address: String,
+    #[arg(short, long)]
+    address: Option<String>,
 
     /// HTTP Server port
-    #[arg(short, long, default_value_t = 8080)]
-    port: u16,
+    #[arg(short, long)]
+    port: Option<u16>,
+
+    /// Snapshot imported at startup when the database is empty
+    #[arg(long)]
+    snapshot: Option<PathBuf>,
+}
 
-    /// Database backend sqlite
-    #[arg(short, long, default_value_t = true)]
-    sqlite: bool,
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ExportFileFormat {
+    Csv,
+    Json,
+    Geojson,
+    /// All the data files of the data source, with their shas
+    Snapshot,
+}
 
-    /// Database backend mongodb
-    #[arg(short, long, default_value_t = false)]
-    mongodb: bool,
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ExportData {
+    Airports,
+    Navaids,
+}
 
-    /// Batabase path. Use ":memory:" for in memory database for
-    #[arg(short, long, default_value = ":memory:")]
-    db_path: String,
+#[derive(Args, Debug)]
+struct ExportArgs {
+    format: ExportFileFormat,
 
-    /// loglevel. 0 for error, 1 for warn, 2 for info, 3 for debug
-    #[arg(short, long, default_value_t = 2)]
-    loglevel: u8,
+    /// Exported data. Ignored for snapshots
+    #[arg(long, value_enum, default_value_t = ExportData::Airports)]
+    data: ExportData,
 
-    /// path to YAML config file
-    #[arg(short, long)]
-    config: PathBuf,
+    /// Output file. Standard output if omitted, except for snapshots. Name snapshots
+    /// *.navdata.zst to use them as data path
+    #[arg(short, long, required_if_eq("format", "snapshot"))]
+    output: Option<PathBuf>,
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    env_logger::init();
-
-    let args = Args::parse();
-
-    let mut config = Config::default();
-    if args.config.exists() {
-        let f = std::fs::File::open(args.config).expect("Could not open file.");
-        config = serde_yaml::from_reader(f).expect("Could not read values.");
-    } else {
-        config.http.host = args.address.clone();
-        config.http.port = args.port;
-
-        config.database.backend = {
-            if args.mongodb {
-                BackendType::MONGODB
-            } else {
-                BackendType::SQLITE
-            }
+#[derive(Subcommand, Debug)]
+enum Query {
+    /// Airport by ICAO code, or airport search
+    Airport(QueryArgs),
+    /// Navaids by ICAO code, or navaid search
+    Navaid(QueryArgs),
+}
+
+#[derive(Args, Debug)]
+struct QueryArgs {
+    /// ICAO code
+    code: Option<String>,
+
+    /// Searched point, as latitude,longitude. Results are sorted by distance
+    #[arg(long, value_parser = parse_point, conflicts_with = "code")]
+    near: Option<(f64, f64)>,
+
+    /// Maximum distance to the searched point, in nautical miles
+    #[arg(long, requires = "near")]
+    radius_nm: Option<f64>,
+
+    /// Text searched in names and codes
+    #[arg(long, conflicts_with = "code")]
+    search: Option<String>,
+
+    /// ISO country code
+    #[arg(long, conflicts_with = "code")]
+    country: Option<String>,
+
+    /// Maximum number of results
+    #[arg(long, default_value_t = 10)]
+    limit: u64,
+}
+
+impl QueryArgs {
+    fn to_filter(&self) -> Result<SearchFilter, String> {
+        let filter = SearchFilter {
+            search: self.search.clone(),
+            limit: Some(self.limit),
+            country: self.country.clone(),
+            latitude: self.near.map(|(latitude, _)| latitude),
+            longitude: self.near.map(|(_, longitude)| longitude),
+            radius_nm: self.radius_nm,
+            ..Default::default()
         };
-        config.database.path = Some(args.db_path.clone());
+        filter.validate()?;
+        Ok(filter)
+    }
+}
+
+/// Parses a `latitude,longitude` string
+fn parse_point(input: &str) -> Result<(f64, f64), String> {
+    let values = input
+        .split(',')
+        .map(|value| value.trim().parse::<f64>())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|_| "Coordinates must be numbers".to_string())?;
+    match values[..] {
+        [latitude, longitude] => Ok((latitude, longitude)),
+        _ => Err("Point must be latitude,longitude".to_string()),
+    }
+}
+
+/// Defaults, config file and environment, overridden by the command line flags
+fn load_config(cli: &Cli) -> Result<Config, ConfigError> {
+    let mut config = Config::load(cli.config.as_deref())?;
+    if let Some(backend) = cli.backend {
+        config.database.backend = backend;
+    }
+    if cli.db_path.is_some() {
+        config.database.path = cli.db_path.clone();
+    }
+    if cli.data_path.is_some() {
+        config.database.data_source.path = cli.data_path.clone();
     }
-    let backend = DatabaseBackend::new(
+    if let Some(Command::Serve(args)) = &cli.command {
+        if let Some(address) = &args.address {
+            config.http.host = address.clone();
+        }
+        if let Some(port) = args.port {
+            config.http.port = port;
+        }
+        if args.snapshot.is_some() {
+            config.database.snapshot = args.snapshot.clone();
+        }
+    }
+    config.validate()?;
+    Ok(config)
+}
+
+async fn open_state(config: Config) -> AppState {
+    let source = DataSource::from(&config.database.data_source);
+    let backend = open_backend(
         config.database.backend,
         config
             .database
@@ -71,27 +207,218 @@ async fn main() -> std::io::Result<()> {
             .unwrap_or(":memory:".to_string()),
     )
     .await;
+    AppState::new(backend, source, config)
+}
+
+/// Loads the data if the database holds none yet
+async fn ensure_loaded(app_state: &AppState) -> Result<(), Box<dyn Error>> {
+    if app_state.database.loaded_files().await?.is_empty() {
+        info!("Database is empty, loading data from the data source");
+        app_state.refresh().await?;
+    }
+    Ok(())
+}
+
+/// Imports the snapshot if the database holds no data yet. Returns the outcome
+/// of the import, or None if it did not happen or failed
+async fn import_snapshot(app_state: &AppState, path: PathBuf) -> Option<Result<(), UpdateError>> {
+    match app_state.database.loaded_files().await {
+        Ok(files) if files.is_empty() => {
+            info!("Importing snapshot {}", path.display());
+            match app_state.refresh_from(&DataSource::Snapshot(path)).await {
+                Ok(()) => Some(Ok(())),
+                Err(err) => {
+                    error!("Snapshot import failed : {}", err);
+                    None
+                }
+            }
+        }
+        Ok(_) => {
+            info!(
+                "Database already holds data, snapshot {} ignored",
+                path.display()
+            );
+            None
+        }
+        Err(err) => {
+            error!("Could not check the database content : {}", err);
+            None
+        }
+    }
+}
+
+async fn serve(config: Config, config_path: Option<PathBuf>) -> std::io::Result<()> {
+    let schedule =
+        Schedule::try_from(&config.database.refresh).expect("Invalid refresh configuration");
+    let startup = config.database.refresh.startup;
 
     let host = config.http.host.clone();
     let port = config.http.port;
+    let cors = config.http.cors.clone();
+    let snapshot = config.database.snapshot.clone();
+    // Shared by all workers, for the quotas and usage counters. Replaced when the
+    // config file changes
+    let security: web::Data<SharedSecurity> =
+        web::Data::new(ArcSwap::from_pointee(Security::new(&config.security).await));
+    if let Some(path) = config_path.filter(|path| path.exists()) {
+        actix_rt::spawn(watch_config(path, security.clone()));
+    }
 
-    let app_state: web::Data<AppState> = web::Data::new(AppState {
-        database: backend,
-        config: config,
-    });
+    let app_state = Arc::new(open_state(config).await);
 
-    actix_rt::spawn(periodical_update(app_state.clone()));
+    let snapshot_load = match snapshot {
+        Some(path) => import_snapshot(&app_state, path).await,
+        None => None,
+    };
+    let startup_load = match (snapshot_load, startup) {
+        (Some(result), _) => Some(result),
+        (None, StartupMode::LOAD_BEFORE_SERVE) => Some(app_state.refresh().await),
+        (None, StartupMode::SERVE_STALE) => None,
+    };
+    actix_rt::spawn(periodical_update(app_state.clone(), schedule, startup_load));
+
+    let app_state = web::Data::from(app_state);
 
     HttpServer::new(move || {
         let logger = Logger::default();
         App::new()
             .wrap(logger)
-            .wrap(Cors::permissive().supports_credentials())
             .wrap(SimpleToken)
+            // Outside of the token check, for preflight requests and error responses
+            .wrap(app::cors(&cors))
             .app_data(app_state.clone())
+            .app_data(security.clone())
             .configure(app::register_routes)
     })
     .bind((host, port))?
     .run()
     .await
 }
+
+async fn import(config: Config) -> Result<(), Box<dyn Error>> {
+    let app_state = open_state(config).await;
+    app_state.refresh().await?;
+    Ok(())
+}
+
+async fn export(config: Config, args: ExportArgs) -> Result<(), Box<dyn Error>> {
+    let format = match args.format {
+        ExportFileFormat::Csv => ExportFormat::Csv,
+        ExportFileFormat::Json => ExportFormat::Json,
+        ExportFileFormat::Geojson => ExportFormat::GeoJson,
+        ExportFileFormat::Snapshot => {
+            let source = DataSource::from(&config.database.data_source);
+            let output = args.output.ok_or("A snapshot needs an output file")?;
+            return Snapshot::from_source(&source).await?.write(&output);
+        }
+    };
+
+    let app_state = open_state(config).await;
+    ensure_loaded(&app_state).await?;
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    match args.data {
+        ExportData::Airports => {
+            // Details are not part of the CSV columns
+            let skip_details = matches!(format, ExportFormat::Csv);
+            let airports = all_airports(app_state.database.as_ref(), skip_details).await?;
+            info!("Exporting {} airports", airports.len());
+            write_items(&airports, format, &mut output)?;
+        }
+        ExportData::Navaids => {
+            let navaids = all_navaids(app_state.database.as_ref()).await?;
+            info!("Exporting {} navaids", navaids.len());
+            write_items(&navaids, format, &mut output)?;
+        }
+    }
+    output.flush()?;
+    Ok(())
+}
+
+async fn query(config: Config, query: Query) -> Result<(), Box<dyn Error>> {
+    let app_state = open_state(config).await;
+    ensure_loaded(&app_state).await?;
+    let database = &app_state.database;
+    match query {
+        Query::Airport(QueryArgs {
+            code: Some(code), ..
+        }) => match database.get_airport_by_icao_code(code.clone()).await? {
+            Some(airport) => print_json(&airport),
+            None => Err(format!("Airport {} not found", code).into()),
+        },
+        Query::Airport(args) => print_json(&database.search_airport(args.to_filter()?).await?),
+        Query::Navaid(QueryArgs {
+            code: Some(code), ..
+        }) => print_json(&database.get_navaids_by_icao_code(code).await?),
+        Query::Navaid(args) => print_json(&database.search_navaid(args.to_filter()?).await?),
+    }
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), Box<dyn Error>> {
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, value)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let cli = Cli::parse();
+
+    let level = match cli.loglevel {
+        0 => "error",
+        1 => "warn",
+        2 => "info",
+        _ => "debug",
+    };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level)).init();
+
+    let config = load_config(&cli).map_err(|err| exit_on_error(err.into()))?;
+    match cli.command {
+        None | Some(Command::Serve(_)) => serve(config, cli.config).await,
+        Some(Command::Import) => import(config).await.map_err(exit_on_error),
+        Some(Command::Export(args)) => export(config, args).await.map_err(exit_on_error),
+        Some(Command::Query(query_args)) => query(config, query_args).await.map_err(exit_on_error),
+    }
+}
+
+/// Reports the failure of a one-off command, exiting with an error code
+fn exit_on_error(err: Box<dyn Error>) -> std::io::Error {
+    eprintln!("Error : {}", err);
+    std::process::exit(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn command_line_flags_override_the_environment() {
+        env::set_var("NAVDATA_PORT", "9100");
+        env::set_var("NAVDATA_BACKEND", "MEMORY");
+        let from_environment = load_config(&Cli::parse_from(["nav_data", "serve"]));
+        let from_flags = load_config(&Cli::parse_from([
+            "nav_data",
+            "--backend",
+            "SQLITE",
+            "--db-path",
+            ":memory:",
+            "serve",
+            "--port",
+            "9200",
+        ]));
+        env::remove_var("NAVDATA_PORT");
+        env::remove_var("NAVDATA_BACKEND");
+
+        let config = from_environment.unwrap();
+        assert_eq!(config.http.port, 9100);
+        assert!(matches!(config.database.backend, BackendType::MEMORY));
+        let config = from_flags.unwrap();
+        assert_eq!(config.http.port, 9200);
+        assert!(matches!(config.database.backend, BackendType::SQLITE));
+        assert_eq!(config.database.path.as_deref(), Some(":memory:"));
+    }
+}
@@ -1,9 +1,15 @@
 // Database
 pub const ERROR_SQLITE_ACCESS: &str = "Error while accessing SQLite connection";
 pub const CSV_FORMAT_ERROR: &str = "CSV file does not have the right format";
+pub const ERROR_UPDATE_STATUS_ACCESS: &str = "Error while accessing update status";
+pub const ERROR_DATASET_META_ACCESS: &str = "Error while accessing dataset metadata";
+
+// Security
+pub const ERROR_TOKEN_USAGE_ACCESS: &str = "Error while accessing token usage";
 
 // Parameters
 pub const TOKEN_COOKIE: &str = "navaid_auth_token";
+pub const API_KEY_HEADER: &str = "X-API-Key";
 
 // HTTP
 pub const HTTP_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
@@ -0,0 +1,232 @@
+//! PostgreSQL backend, against a local server with PostGIS:
+//!
+//! ```sh
+//! docker run -d -p 5432:5432 -e POSTGRES_PASSWORD=postgres postgis/postgis
+//! cargo test --test postgres -- --ignored
+//! ```
+//!
+//! Another server is given with `NAVDATA_TEST_POSTGRES_URL`
+mod common;
+
+use common::{loaded_backend, postgres_url};
+use navdata::{BackendType, BoundingBox, NavDataStore, SearchFilter};
+
+async fn postgres() -> Box<dyn NavDataStore> {
+    loaded_backend(BackendType::POSTGRES, postgres_url()).await
+}
+
+fn codes<T>(items: &[T], code: fn(&T) -> &String) -> Vec<String> {
+    items.iter().map(|item| code(item).clone()).collect()
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn airport_is_found_by_icao_code_with_its_details() {
+    let store = postgres().await;
+    let airport = store
+        .get_airport_by_icao_code("lfpg".to_string())
+        .await
+        .unwrap()
+        .expect("LFPG not found");
+    assert_eq!(airport.name, "Charles de Gaulle");
+    assert_eq!(airport.iata_code, "CDG");
+    assert_eq!(
+        (airport.location.latitude(), airport.location.longitude()),
+        (49.0097, 2.5479)
+    );
+    assert_eq!(
+        codes(&airport.runways, |runway| &runway.le_ident),
+        ["08L", "09R"]
+    );
+    assert_eq!(airport.frequencies.len(), 2);
+    assert_eq!(codes(&airport.navaids, |navaid| &navaid.icao_code), ["CGN"]);
+
+    let unknown = store.get_airport_by_icao_code("ZZZZ".to_string()).await;
+    assert!(unknown.unwrap().is_none());
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn searched_airports_get_their_own_details() {
+    let store = postgres().await;
+    let filter = SearchFilter {
+        country: Some("fr".to_string()),
+        ..Default::default()
+    };
+    let mut airports = store.search_airport(filter).await.unwrap();
+    airports.sort_by(|a, b| a.icao_code.cmp(&b.icao_code));
+    assert_eq!(
+        codes(&airports, |airport| &airport.icao_code),
+        ["LFPG", "LFPN", "LFPO"]
+    );
+    for airport in &airports {
+        assert!(!airport.runways.is_empty());
+        assert!(airport
+            .runways
+            .iter()
+            .all(|runway| runway.airport_icao_code == airport.icao_code));
+        assert!(airport
+            .frequencies
+            .iter()
+            .all(|frequency| frequency.airport_icao_code == airport.icao_code));
+    }
+    assert_eq!(airports[0].runways.len(), 2);
+    assert_eq!(airports[2].frequencies.len(), 1);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn airports_can_be_searched_without_details() {
+    let store = postgres().await;
+    let filter = SearchFilter {
+        search: Some("LFPG".to_string()),
+        skip_details: true,
+        ..Default::default()
+    };
+    let airports = store.search_airport(filter).await.unwrap();
+    assert_eq!(codes(&airports, |airport| &airport.icao_code), ["LFPG"]);
+    assert!(airports[0].runways.is_empty());
+    assert!(airports[0].frequencies.is_empty());
+    assert!(airports[0].navaids.is_empty());
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn text_search_matches_substrings_ignoring_case() {
+    let store = postgres().await;
+    for (search, expected) in [
+        ("FPG", vec!["LFPG"]),
+        ("gaulle", vec!["LFPG"]),
+        ("ORY", vec!["LFPO"]),
+    ] {
+        let filter = SearchFilter {
+            search: Some(search.to_string()),
+            ..Default::default()
+        };
+        let airports = store.search_airport(filter).await.unwrap();
+        assert_eq!(
+            codes(&airports, |airport| &airport.icao_code),
+            expected,
+            "{}",
+            search
+        );
+    }
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn proximity_search_is_ordered_by_distance() {
+    let store = postgres().await;
+    let filter = SearchFilter {
+        latitude: Some(48.85),
+        longitude: Some(2.35),
+        radius_nm: Some(50.0),
+        ..Default::default()
+    };
+    let airports = store.search_airport(filter).await.unwrap();
+    assert_eq!(
+        codes(&airports, |airport| &airport.icao_code),
+        ["LFPO", "LFPN", "LFPG"]
+    );
+    let distances = airports
+        .iter()
+        .map(|airport| airport.distance_nm.expect("distance not set"))
+        .collect::<Vec<f64>>();
+    assert!(distances.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(distances.iter().all(|distance| *distance <= 50.0));
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn proximity_search_wraps_around_the_antimeridian() {
+    let store = postgres().await;
+    let filter = SearchFilter {
+        latitude: Some(-20.0),
+        longitude: Some(180.0),
+        radius_nm: Some(400.0),
+        ..Default::default()
+    };
+    let airports = store.search_airport(filter).await.unwrap();
+    let mut found = codes(&airports, |airport| &airport.icao_code);
+    found.sort();
+    assert_eq!(found, ["NFFN", "NFTF"]);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn bounding_box_search_crosses_the_antimeridian() {
+    let store = postgres().await;
+    let filter = SearchFilter {
+        bbox: Some(BoundingBox {
+            min_lon: 170.0,
+            min_lat: -25.0,
+            max_lon: -170.0,
+            max_lat: -10.0,
+        }),
+        ..Default::default()
+    };
+    let navaids = store.search_navaid(filter).await.unwrap();
+    let mut found = codes(&navaids, |navaid| &navaid.icao_code);
+    found.sort();
+    assert_eq!(found, ["NN", "TBU"]);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn navaids_are_searched_by_type_and_paginated() {
+    let store = postgres().await;
+    let navaids = store
+        .get_navaids_by_icao_code("cgn".to_string())
+        .await
+        .unwrap();
+    assert_eq!(
+        codes(&navaids, |navaid| &navaid.associated_airport),
+        ["LFPG"]
+    );
+
+    let filter = SearchFilter {
+        r#type: Some("vor-dme".to_string()),
+        ..Default::default()
+    };
+    let mut navaids = store.search_navaid(filter.clone()).await.unwrap();
+    navaids.sort_by(|a, b| a.icao_code.cmp(&b.icao_code));
+    assert_eq!(codes(&navaids, |navaid| &navaid.icao_code), ["CGN", "EPR"]);
+
+    let first = SearchFilter {
+        limit: Some(1),
+        ..filter.clone()
+    };
+    let second = SearchFilter {
+        page: Some(1),
+        ..first.clone()
+    };
+    let first = store.search_navaid(first).await.unwrap();
+    let second = store.search_navaid(second).await.unwrap();
+    assert_eq!(first.len(), 1);
+    assert_eq!(second.len(), 1);
+    assert_ne!(first[0].icao_code, second[0].icao_code);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn loaded_files_are_recorded() {
+    let store = postgres().await;
+    let mut files = store
+        .loaded_files()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|file| file.file)
+        .collect::<Vec<String>>();
+    files.sort();
+    assert_eq!(
+        files,
+        [
+            "airport-frequencies.csv",
+            "airports.csv",
+            "navaids.csv",
+            "runways.csv"
+        ]
+    );
+    assert!(!store.is_refreshing());
+}
@@ -0,0 +1,143 @@
+//! The SQL backends answer the same searches as the memory backend, with the same
+//! airports and navaids
+mod common;
+
+use common::{loaded_backend, postgres_url};
+use navdata::{BackendType, BoundingBox, NavDataStore, SearchFilter};
+
+fn text(search: &str) -> SearchFilter {
+    SearchFilter {
+        search: Some(search.to_string()),
+        ..Default::default()
+    }
+}
+
+fn around(latitude: f64, longitude: f64, radius_nm: f64) -> SearchFilter {
+    SearchFilter {
+        latitude: Some(latitude),
+        longitude: Some(longitude),
+        radius_nm: Some(radius_nm),
+        ..Default::default()
+    }
+}
+
+fn searches() -> Vec<SearchFilter> {
+    vec![
+        SearchFilter::default(),
+        text("FPG"),
+        text("lfpg"),
+        text("gaulle"),
+        text("aulle"),
+        text("de g"),
+        text("LF"),
+        text("T"),
+        text("Fua'amotu"),
+        text("zzz"),
+        SearchFilter {
+            country: Some("fr".to_string()),
+            ..text("o")
+        },
+        SearchFilter {
+            r#type: Some("vor-dme".to_string()),
+            ..Default::default()
+        },
+        SearchFilter {
+            r#type: Some("small_airport".to_string()),
+            ..Default::default()
+        },
+        around(48.85, 2.35, 50.0),
+        around(-20.0, 180.0, 400.0),
+        SearchFilter {
+            limit: Some(1),
+            page: Some(1),
+            ..around(48.85, 2.35, 50.0)
+        },
+        SearchFilter {
+            bbox: Some(BoundingBox {
+                min_lon: 170.0,
+                min_lat: -25.0,
+                max_lon: -170.0,
+                max_lat: -10.0,
+            }),
+            ..Default::default()
+        },
+        SearchFilter {
+            bbox: Some(BoundingBox {
+                min_lon: 1.0,
+                min_lat: 48.0,
+                max_lon: 3.0,
+                max_lat: 49.0,
+            }),
+            ..text("o")
+        },
+    ]
+}
+
+/// Codes of the airports and navaids found by each search. Proximity searches
+/// are compared in order, other searches have no defined order
+async fn results(store: &dyn NavDataStore) -> Vec<(Vec<String>, Vec<String>)> {
+    let mut results = vec![];
+    for filter in searches() {
+        let ordered = filter.center().is_some();
+        let mut airports = store
+            .search_airport(filter.clone())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|airport| airport.icao_code)
+            .collect::<Vec<String>>();
+        let mut navaids = store
+            .search_navaid(filter)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|navaid| navaid.icao_code)
+            .collect::<Vec<String>>();
+        if !ordered {
+            airports.sort();
+            navaids.sort();
+        }
+        results.push((airports, navaids));
+    }
+    results
+}
+
+async fn memory_results() -> Vec<(Vec<String>, Vec<String>)> {
+    let store = loaded_backend(BackendType::MEMORY, String::new()).await;
+    results(store.as_ref()).await
+}
+
+#[actix_rt::test]
+async fn memory_backend_matches_substrings() {
+    let store = loaded_backend(BackendType::MEMORY, String::new()).await;
+    for (search, expected) in [
+        ("FPG", vec!["LFPG"]),
+        ("aulle", vec!["LFPG"]),
+        ("DE G", vec!["LFPG"]),
+        ("LF", vec!["LFPG", "LFPN", "LFPO"]),
+        ("zzz", vec![]),
+    ] {
+        let mut airports = store
+            .search_airport(text(search))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|airport| airport.icao_code)
+            .collect::<Vec<String>>();
+        airports.sort();
+        assert_eq!(airports, expected, "{}", search);
+    }
+}
+
+#[actix_rt::test]
+async fn sqlite_and_memory_backends_find_the_same_items() {
+    let store = loaded_backend(BackendType::SQLITE, ":memory:".to_string()).await;
+    assert_eq!(results(store.as_ref()).await, memory_results().await);
+}
+
+#[actix_rt::test]
+#[ignore = "needs a PostgreSQL server"]
+async fn postgres_and_memory_backends_find_the_same_items() {
+    let store = loaded_backend(BackendType::POSTGRES, postgres_url()).await;
+    assert_eq!(results(store.as_ref()).await, memory_results().await);
+}